@@ -0,0 +1,217 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use flate2::read::GzEncoder;
+use flate2::Compression;
+
+pub use tar::TarArchive;
+pub use zip::ZipArchive;
+
+use crate::Body;
+
+mod tar;
+mod zip;
+
+/// Formats a directory can be downloaded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Zip,
+    TarGz,
+}
+
+impl Format {
+    /// Parses the value of the `download` query parameter
+    pub fn parse(format: &str) -> Option<Format> {
+        match format {
+            "zip" => Some(Format::Zip),
+            "tar.gz" | "tgz" => Some(Format::TarGz),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Zip => "zip",
+            Format::TarGz => "tar.gz",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Zip => "application/zip",
+            Format::TarGz => "application/gzip",
+        }
+    }
+
+    /// Creates a body which generates the archive as it is sent
+    pub fn body(&self, entries: Vec<Entry>) -> Body {
+        match self {
+            Format::Zip => Body::Chunked(Box::new(ZipArchive::new(entries))),
+            Format::TarGz => Body::Chunked(Box::new(GzEncoder::new(
+                TarArchive::new(entries),
+                Compression::default(),
+            ))),
+        }
+    }
+}
+
+/// A file or directory to be added to an archive
+pub struct Entry {
+    /// Location of the file on disk
+    pub path: PathBuf,
+    /// Path within the archive, using `/` as the separator
+    pub name: String,
+    pub is_dir: bool,
+    /// Size in bytes, which is always 0 for directories
+    pub size: u64,
+    /// Seconds since the Unix epoch
+    pub modified: u64,
+}
+
+/// Collects every file and directory under `dir`,
+/// with archive paths starting with `root`.
+///
+/// Only the metadata is read,
+/// so files are not opened until they are reached while sending.
+/// Entries which cannot be read, or which `include` rejects, are left out,
+/// along with everything beneath them.
+/// Symlinks are followed wherever `include` allows,
+/// except into a directory which is already being walked, so that loops cannot occur.
+pub fn walk<F>(dir: &Path, root: &str, include: &F) -> io::Result<Vec<Entry>>
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut entries = vec![Entry {
+        path: dir.to_path_buf(),
+        name: format!("{}/", root),
+        is_dir: true,
+        size: 0,
+        modified: modified_secs(&fs::metadata(dir)?),
+    }];
+
+    let mut ancestors = vec![dir.canonicalize()?];
+    walk_into(dir, root, include, &mut ancestors, &mut entries)?;
+
+    Ok(entries)
+}
+
+fn walk_into<F>(
+    dir: &Path,
+    prefix: &str,
+    include: &F,
+    ancestors: &mut Vec<PathBuf>,
+    entries: &mut Vec<Entry>,
+) -> io::Result<()>
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut children = dir.read_dir()?.filter_map(Result::ok).collect::<Vec<_>>();
+
+    // archives list entries in a stable order
+    children.sort_by_key(|child| child.file_name());
+
+    for child in children {
+        let path = child.path();
+
+        if !include(&path) {
+            continue;
+        }
+
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        let name = format!("{}/{}", prefix, child.file_name().to_string_lossy());
+
+        if metadata.is_dir() {
+            entries.push(Entry {
+                path: path.clone(),
+                name: format!("{}/", name),
+                is_dir: true,
+                size: 0,
+                modified: modified_secs(&metadata),
+            });
+
+            let target = match path.canonicalize() {
+                Ok(target) if !ancestors.contains(&target) => target,
+                _ => continue,
+            };
+
+            // directories which cannot be read are left empty
+            ancestors.push(target);
+            let _ = walk_into(&path, name.as_str(), include, ancestors, entries);
+            ancestors.pop();
+        } else if metadata.is_file() {
+            entries.push(Entry {
+                path,
+                name,
+                is_dir: false,
+                size: metadata.len(),
+                modified: modified_secs(&metadata),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .unwrap_or(UNIX_EPOCH)
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs())
+}
+
+/// Reads exactly `size` bytes of a file,
+/// opening it only when the first byte is needed.
+///
+/// The size was recorded when the archive was planned,
+/// so if the file has since shrunk the rest is filled with zeros,
+/// and if it has grown the extra bytes are left out.
+/// This keeps the archive well-formed.
+struct FileReader {
+    path: PathBuf,
+    file: Option<File>,
+    remaining: u64,
+}
+
+impl FileReader {
+    fn new(path: PathBuf, size: u64) -> FileReader {
+        FileReader {
+            path,
+            file: None,
+            remaining: size,
+        }
+    }
+}
+
+impl Read for FileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let max = buf.len().min(self.remaining as usize);
+
+        if self.file.is_none() {
+            self.file = Some(File::open(&self.path)?);
+        }
+
+        let read = match self.file.as_mut().unwrap().read(&mut buf[..max])? {
+            0 => {
+                buf[..max].iter_mut().for_each(|byte| *byte = 0);
+                max
+            }
+            read => read,
+        };
+
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::prelude::*;
+use std::io::Cursor;
+
+use crate::archive::{Entry, FileReader};
+
+/// Size of a tar block, which headers and file contents are padded to
+const BLOCK_SIZE: usize = 512;
+
+/// Largest size which fits in the octal size field of a header
+const MAX_OCTAL_SIZE: u64 = 0o77777777777;
+
+/// Generates an uncompressed tar archive as it is read,
+/// using the POSIX ustar format with GNU extensions
+/// for long paths and large files.
+pub struct TarArchive {
+    entries: VecDeque<Entry>,
+    /// Headers and padding waiting to be read
+    buffer: Cursor<Vec<u8>>,
+    /// The file currently being read, along with the padding which follows it
+    current: Option<(FileReader, usize)>,
+    finished: bool,
+}
+
+impl TarArchive {
+    pub fn new(entries: Vec<Entry>) -> TarArchive {
+        TarArchive {
+            entries: entries.into(),
+            buffer: Cursor::new(Vec::new()),
+            current: None,
+            finished: false,
+        }
+    }
+
+    /// Queues the headers for an entry,
+    /// and starts reading its contents if it is a file.
+    fn start_entry(&mut self, entry: Entry) {
+        let mut bytes = Vec::new();
+
+        let name = entry.name.as_bytes();
+        let (prefix, short_name) = match split_name(name) {
+            Some(split) => split,
+            None => {
+                // the real name is stored in a preceding entry of its own
+                let mut long_name = name.to_vec();
+                long_name.push(0);
+
+                bytes.extend(header(
+                    b"",
+                    b"././@LongLink",
+                    long_name.len() as u64,
+                    0,
+                    b'L',
+                ));
+                bytes.extend(&long_name);
+                bytes.resize(padded(bytes.len()), 0);
+
+                (&b""[..], &name[..name.len().min(100)])
+            }
+        };
+
+        let type_flag = if entry.is_dir { b'5' } else { b'0' };
+        bytes.extend(header(
+            prefix,
+            short_name,
+            entry.size,
+            entry.modified,
+            type_flag,
+        ));
+
+        self.buffer = Cursor::new(bytes);
+
+        if !entry.is_dir {
+            let padding = padded(entry.size as usize) - entry.size as usize;
+            self.current = Some((FileReader::new(entry.path, entry.size), padding));
+        }
+    }
+}
+
+impl Read for TarArchive {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let read = self.buffer.read(buf)?;
+            if read > 0 {
+                return Ok(read);
+            }
+
+            if let Some((reader, padding)) = self.current.as_mut() {
+                let read = reader.read(buf)?;
+                if read > 0 {
+                    return Ok(read);
+                }
+
+                self.buffer = Cursor::new(vec![0; *padding]);
+                self.current = None;
+                continue;
+            }
+
+            match self.entries.pop_front() {
+                Some(entry) => self.start_entry(entry),
+                None if !self.finished => {
+                    // the archive ends with two empty blocks
+                    self.buffer = Cursor::new(vec![0; BLOCK_SIZE * 2]);
+                    self.finished = true;
+                }
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Rounds a length up to a whole number of blocks
+fn padded(length: usize) -> usize {
+    length.div_ceil(BLOCK_SIZE) * BLOCK_SIZE
+}
+
+/// Splits a path into the prefix and name fields of a ustar header,
+/// or returns `None` if it is too long to fit.
+fn split_name(name: &[u8]) -> Option<(&[u8], &[u8])> {
+    if name.len() <= 100 {
+        return Some((b"", name));
+    }
+
+    // the split must be at a slash, which is not stored
+    (0..name.len())
+        .filter(|&i| name[i] == b'/')
+        .map(|i| (&name[..i], &name[i + 1..]))
+        .find(|(prefix, rest)| prefix.len() <= 155 && rest.len() <= 100 && !rest.is_empty())
+}
+
+/// Builds a single header block
+fn header(prefix: &[u8], name: &[u8], size: u64, modified: u64, type_flag: u8) -> Vec<u8> {
+    let mut header = vec![0; BLOCK_SIZE];
+
+    header[..name.len()].copy_from_slice(name);
+
+    // permissions are not copied, so as not to reveal anything about the host
+    let mode = if type_flag == b'5' { 0o755 } else { 0o644 };
+    write_octal(&mut header[100..108], mode);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+
+    if size > MAX_OCTAL_SIZE {
+        // GNU base-256 encoding, flagged by the high bit
+        header[124] = 0x80;
+        header[128..136].copy_from_slice(&size.to_be_bytes());
+    } else {
+        write_octal(&mut header[124..136], size);
+    }
+
+    write_octal(&mut header[136..148], modified.min(MAX_OCTAL_SIZE));
+    header[156] = type_flag;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix);
+
+    // the checksum is calculated as if its own field were spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum = header.iter().map(|&byte| byte as u64).sum::<u64>();
+    write_octal(&mut header[148..155], checksum);
+    header[155] = b' ';
+
+    header
+}
+
+/// Writes a zero-padded, NUL-terminated octal number into a header field
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    let last = field.len() - 1;
+
+    field[..last].copy_from_slice(&digits.as_bytes()[digits.len() - last..]);
+    field[last] = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::path_utils::random_string;
+
+    /// An entry read back from an archive
+    struct Parsed {
+        name: String,
+        type_flag: u8,
+        contents: Vec<u8>,
+    }
+
+    fn field(bytes: &[u8]) -> String {
+        let end = bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(bytes.len());
+        String::from_utf8(bytes[..end].to_vec()).unwrap()
+    }
+
+    fn octal(bytes: &[u8]) -> u64 {
+        u64::from_str_radix(field(bytes).trim(), 8).unwrap()
+    }
+
+    /// Reads an archive back, checking each header's checksum
+    /// and that it ends with two empty blocks
+    fn parse(tar: &[u8]) -> Vec<Parsed> {
+        assert_eq!(tar.len() % BLOCK_SIZE, 0);
+
+        let mut parsed = Vec::new();
+        let mut long_name = None;
+        let mut at = 0;
+
+        while tar[at..at + BLOCK_SIZE].iter().any(|&byte| byte != 0) {
+            let header = &tar[at..at + BLOCK_SIZE];
+
+            let mut unsummed = header.to_vec();
+            unsummed[148..156].copy_from_slice(b"        ");
+            let checksum = unsummed.iter().map(|&byte| byte as u64).sum::<u64>();
+            assert_eq!(octal(&header[148..155]), checksum);
+            assert_eq!(&header[257..263], b"ustar\0");
+
+            let size = octal(&header[124..136]) as usize;
+            let contents = tar[at + BLOCK_SIZE..at + BLOCK_SIZE + size].to_vec();
+            at += BLOCK_SIZE + padded(size);
+
+            if header[156] == b'L' {
+                long_name = Some(field(&contents));
+                continue;
+            }
+
+            let prefix = field(&header[345..500]);
+            let name = long_name.take().unwrap_or_else(|| {
+                let name = field(&header[..100]);
+                if prefix.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", prefix, name)
+                }
+            });
+
+            parsed.push(Parsed {
+                name,
+                type_flag: header[156],
+                contents,
+            });
+        }
+
+        assert_eq!(&tar[at..], &[0; BLOCK_SIZE * 2][..]);
+        parsed
+    }
+
+    fn read_all(entries: Vec<Entry>) -> Vec<u8> {
+        let mut tar = Vec::new();
+        TarArchive::new(entries).read_to_end(&mut tar).unwrap();
+        tar
+    }
+
+    fn entry(path: PathBuf, name: &str, size: u64) -> Entry {
+        Entry {
+            path,
+            name: name.to_string(),
+            is_dir: false,
+            size,
+            modified: 0,
+        }
+    }
+
+    #[test]
+    fn round_trips_files_and_directories() {
+        let path = std::env::temp_dir().join(random_string(12));
+        fs::write(&path, "hello").unwrap();
+
+        let parsed = parse(&read_all(vec![
+            Entry {
+                path: path.clone(),
+                name: "dir/".to_string(),
+                is_dir: true,
+                size: 0,
+                modified: 0,
+            },
+            entry(path.clone(), "dir/a.txt", 5),
+        ]));
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(
+            (parsed[0].name.as_str(), parsed[0].type_flag),
+            ("dir/", b'5')
+        );
+        assert_eq!(
+            (parsed[1].name.as_str(), parsed[1].type_flag),
+            ("dir/a.txt", b'0')
+        );
+        assert_eq!(parsed[1].contents, b"hello");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn splits_long_paths_into_prefix_and_name() {
+        let name = format!("{}/{}", "d".repeat(120), "f".repeat(90));
+        let (prefix, short_name) = split_name(name.as_bytes()).unwrap();
+        assert_eq!(prefix, "d".repeat(120).as_bytes());
+        assert_eq!(short_name, "f".repeat(90).as_bytes());
+
+        let tar = read_all(vec![entry(PathBuf::new(), name.as_str(), 0)]);
+        // no separate entry is needed for the name
+        assert_eq!(tar.len(), BLOCK_SIZE * 3);
+        assert_eq!(parse(&tar)[0].name, name);
+    }
+
+    #[test]
+    fn stores_unsplittable_paths_in_a_long_link() {
+        let name = format!("{}/{}", "d".repeat(20), "f".repeat(150));
+        assert!(split_name(name.as_bytes()).is_none());
+
+        let tar = read_all(vec![entry(PathBuf::new(), name.as_str(), 0)]);
+        assert_eq!(field(&tar[..100]), "././@LongLink");
+        assert_eq!(tar[156], b'L');
+
+        let parsed = parse(&tar);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].name, name);
+    }
+
+    #[test]
+    fn pads_files_which_shrink() {
+        let path = std::env::temp_dir().join(random_string(12));
+        fs::write(&path, "abc").unwrap();
+
+        let parsed = parse(&read_all(vec![
+            entry(path.clone(), "shrunk.txt", 600),
+            entry(path.clone(), "next.txt", 3),
+        ]));
+
+        let mut expected = b"abc".to_vec();
+        expected.resize(600, 0);
+        assert_eq!(parsed[0].contents, expected);
+        assert_eq!(parsed[1].name, "next.txt");
+        assert_eq!(parsed[1].contents, b"abc");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn encodes_large_sizes_in_base_256() {
+        let header = header(b"", b"big", MAX_OCTAL_SIZE + 1, 0, b'0');
+        assert_eq!(header[124], 0x80);
+        assert_eq!(&header[128..136], &(MAX_OCTAL_SIZE + 1).to_be_bytes());
+    }
+}
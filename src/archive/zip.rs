@@ -0,0 +1,474 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::prelude::*;
+use std::io::Cursor;
+
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use flate2::Crc;
+
+use crate::archive::{Entry, FileReader};
+
+/// Value stored in 32-bit fields whose real value is in the ZIP64 extra field
+const ZIP64_MARKER: u32 = 0xFFFF_FFFF;
+
+/// Sizes are given in a data descriptor after the contents,
+/// and names are UTF-8
+const FLAGS: u16 = 0x0008 | 0x0800;
+
+/// Details of an entry needed again for the central directory
+struct Record {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    crc: u32,
+    time: u16,
+    date: u16,
+    offset: u64,
+}
+
+/// A file whose contents are being read
+struct Current {
+    record: Record,
+    reader: FileReader,
+    crc: Crc,
+}
+
+/// Generates a zip archive as it is read.
+///
+/// Files are stored without compression,
+/// so the archive can be written in a single pass
+/// with each checksum following the contents it covers.
+/// ZIP64 records are used where sizes or offsets do not fit in 32 bits.
+pub struct ZipArchive {
+    entries: VecDeque<Entry>,
+    /// Headers waiting to be read
+    buffer: Cursor<Vec<u8>>,
+    current: Option<Current>,
+    records: Vec<Record>,
+    /// Number of bytes generated so far
+    offset: u64,
+    finished: bool,
+}
+
+impl ZipArchive {
+    pub fn new(entries: Vec<Entry>) -> ZipArchive {
+        ZipArchive {
+            entries: entries.into(),
+            buffer: Cursor::new(Vec::new()),
+            current: None,
+            records: Vec::new(),
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    /// Queues bytes to be read, keeping track of the offset
+    fn queue(&mut self, bytes: Vec<u8>) {
+        self.offset += bytes.len() as u64;
+        self.buffer = Cursor::new(bytes);
+    }
+
+    /// Queues the local header for an entry,
+    /// and starts reading its contents if it is a file.
+    fn start_entry(&mut self, entry: Entry) {
+        let (time, date) = dos_date_time(entry.modified);
+
+        let record = Record {
+            name: entry.name,
+            is_dir: entry.is_dir,
+            size: entry.size,
+            crc: 0,
+            time,
+            date,
+            offset: self.offset,
+        };
+
+        let zip64 = record.size >= ZIP64_MARKER as u64;
+        let mut extra = Vec::new();
+        if zip64 {
+            extra.extend(&1u16.to_le_bytes());
+            extra.extend(&16u16.to_le_bytes());
+            extra.extend(&record.size.to_le_bytes());
+            extra.extend(&record.size.to_le_bytes());
+        }
+
+        let size = if zip64 {
+            ZIP64_MARKER
+        } else {
+            record.size as u32
+        };
+
+        let mut bytes = Vec::new();
+        bytes.extend(&0x0403_4b50u32.to_le_bytes());
+        bytes.extend(&version_needed(zip64).to_le_bytes());
+        bytes.extend(&FLAGS.to_le_bytes());
+        // stored, without compression
+        bytes.extend(&0u16.to_le_bytes());
+        bytes.extend(&record.time.to_le_bytes());
+        bytes.extend(&record.date.to_le_bytes());
+        // the checksum is not known until the contents have been read
+        bytes.extend(&0u32.to_le_bytes());
+        bytes.extend(&size.to_le_bytes());
+        bytes.extend(&size.to_le_bytes());
+        bytes.extend(&(record.name.len() as u16).to_le_bytes());
+        bytes.extend(&(extra.len() as u16).to_le_bytes());
+        bytes.extend(record.name.as_bytes());
+        bytes.extend(&extra);
+
+        if entry.is_dir {
+            bytes.extend(descriptor(&record));
+            self.records.push(record);
+            self.queue(bytes);
+        } else {
+            self.queue(bytes);
+            self.current = Some(Current {
+                reader: FileReader::new(entry.path, record.size),
+                record,
+                crc: Crc::new(),
+            });
+        }
+    }
+
+    /// Queues the data descriptor which follows a file's contents
+    fn finish_file(&mut self, current: Current) {
+        let mut record = current.record;
+        record.crc = current.crc.sum();
+
+        self.queue(descriptor(&record));
+        self.records.push(record);
+    }
+
+    /// Queues the central directory, which ends the archive
+    fn finish_archive(&mut self) {
+        let start = self.offset;
+        let mut bytes = Vec::new();
+
+        for record in &self.records {
+            let mut extra = Vec::new();
+
+            let size = if record.size >= ZIP64_MARKER as u64 {
+                extra.extend(&record.size.to_le_bytes());
+                extra.extend(&record.size.to_le_bytes());
+                ZIP64_MARKER
+            } else {
+                record.size as u32
+            };
+
+            let offset = if record.offset >= ZIP64_MARKER as u64 {
+                extra.extend(&record.offset.to_le_bytes());
+                ZIP64_MARKER
+            } else {
+                record.offset as u32
+            };
+
+            if !extra.is_empty() {
+                let mut header = Vec::new();
+                header.extend(&1u16.to_le_bytes());
+                header.extend(&(extra.len() as u16).to_le_bytes());
+                extra.splice(0..0, header);
+            }
+
+            // Unix permissions in the high half, and the MS-DOS directory flag
+            let attributes = if record.is_dir {
+                (0o040755 << 16) | 0x10
+            } else {
+                0o100644 << 16
+            };
+
+            let zip64 = !extra.is_empty();
+
+            bytes.extend(&0x0201_4b50u32.to_le_bytes());
+            // made by Unix
+            bytes.extend(&(0x0300 | version_needed(zip64)).to_le_bytes());
+            bytes.extend(&version_needed(zip64).to_le_bytes());
+            bytes.extend(&FLAGS.to_le_bytes());
+            bytes.extend(&0u16.to_le_bytes());
+            bytes.extend(&record.time.to_le_bytes());
+            bytes.extend(&record.date.to_le_bytes());
+            bytes.extend(&record.crc.to_le_bytes());
+            bytes.extend(&size.to_le_bytes());
+            bytes.extend(&size.to_le_bytes());
+            bytes.extend(&(record.name.len() as u16).to_le_bytes());
+            bytes.extend(&(extra.len() as u16).to_le_bytes());
+            // comment length, disk number and internal attributes
+            bytes.extend(&[0; 6]);
+            bytes.extend(&(attributes as u32).to_le_bytes());
+            bytes.extend(&offset.to_le_bytes());
+            bytes.extend(record.name.as_bytes());
+            bytes.extend(&extra);
+        }
+
+        let count = self.records.len() as u64;
+        let size = bytes.len() as u64;
+
+        if count >= 0xFFFF || size >= ZIP64_MARKER as u64 || start >= ZIP64_MARKER as u64 {
+            let end_offset = start + size;
+
+            // ZIP64 end of central directory record
+            bytes.extend(&0x0606_4b50u32.to_le_bytes());
+            bytes.extend(&44u64.to_le_bytes());
+            bytes.extend(&(0x0300 | version_needed(true)).to_le_bytes());
+            bytes.extend(&version_needed(true).to_le_bytes());
+            bytes.extend(&[0; 8]);
+            bytes.extend(&count.to_le_bytes());
+            bytes.extend(&count.to_le_bytes());
+            bytes.extend(&size.to_le_bytes());
+            bytes.extend(&start.to_le_bytes());
+
+            // ZIP64 end of central directory locator
+            bytes.extend(&0x0706_4b50u32.to_le_bytes());
+            bytes.extend(&0u32.to_le_bytes());
+            bytes.extend(&end_offset.to_le_bytes());
+            bytes.extend(&1u32.to_le_bytes());
+        }
+
+        bytes.extend(&0x0605_4b50u32.to_le_bytes());
+        bytes.extend(&[0; 4]);
+        bytes.extend(&(count.min(0xFFFF) as u16).to_le_bytes());
+        bytes.extend(&(count.min(0xFFFF) as u16).to_le_bytes());
+        bytes.extend(&(size.min(ZIP64_MARKER as u64) as u32).to_le_bytes());
+        bytes.extend(&(start.min(ZIP64_MARKER as u64) as u32).to_le_bytes());
+        bytes.extend(&0u16.to_le_bytes());
+
+        self.queue(bytes);
+    }
+}
+
+impl Read for ZipArchive {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let read = self.buffer.read(buf)?;
+            if read > 0 {
+                return Ok(read);
+            }
+
+            if let Some(current) = self.current.as_mut() {
+                let read = current.reader.read(buf)?;
+                if read > 0 {
+                    current.crc.update(&buf[..read]);
+                    self.offset += read as u64;
+                    return Ok(read);
+                }
+
+                let current = self.current.take().unwrap();
+                self.finish_file(current);
+                continue;
+            }
+
+            match self.entries.pop_front() {
+                Some(entry) => self.start_entry(entry),
+                None if !self.finished => {
+                    self.finish_archive();
+                    self.finished = true;
+                }
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Builds the data descriptor which follows an entry's contents
+fn descriptor(record: &Record) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(&0x0807_4b50u32.to_le_bytes());
+    bytes.extend(&record.crc.to_le_bytes());
+
+    if record.size >= ZIP64_MARKER as u64 {
+        bytes.extend(&record.size.to_le_bytes());
+        bytes.extend(&record.size.to_le_bytes());
+    } else {
+        bytes.extend(&(record.size as u32).to_le_bytes());
+        bytes.extend(&(record.size as u32).to_le_bytes());
+    }
+
+    bytes
+}
+
+/// Gets the version of the format needed to extract an entry
+fn version_needed(zip64: bool) -> u16 {
+    if zip64 {
+        45
+    } else {
+        20
+    }
+}
+
+/// Converts seconds since the Unix epoch into MS-DOS time and date fields.
+///
+/// MS-DOS dates cannot be earlier than 1980, so older times are clamped.
+fn dos_date_time(secs: u64) -> (u16, u16) {
+    let date_time = NaiveDateTime::from_timestamp_opt(secs as i64, 0)
+        .filter(|date_time| date_time.year() >= 1980)
+        .unwrap_or_else(|| NaiveDateTime::from_timestamp_opt(315_532_800, 0).unwrap());
+
+    let time = (date_time.hour() << 11) | (date_time.minute() << 5) | (date_time.second() / 2);
+    let date =
+        (((date_time.year() - 1980) as u32) << 9) | (date_time.month() << 5) | date_time.day();
+
+    (time as u16, date as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use crate::archive::walk;
+    use crate::path_utils::random_string;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    }
+
+    fn crc(bytes: &[u8]) -> u32 {
+        let mut crc = Crc::new();
+        crc.update(bytes);
+        crc.sum()
+    }
+
+    /// An entry as described by the central directory,
+    /// along with the contents found at its local header
+    struct Parsed {
+        name: String,
+        crc: u32,
+        contents: Vec<u8>,
+    }
+
+    /// Reads an archive using only the central directory to find each entry,
+    /// checking that it agrees with the local headers and data descriptors.
+    fn parse(zip: &[u8]) -> Vec<Parsed> {
+        let end = (0..=zip.len() - 22)
+            .rev()
+            .find(|&i| u32_at(zip, i) == 0x0605_4b50)
+            .expect("missing end of central directory");
+
+        let count = u16_at(zip, end + 10) as usize;
+        let size = u32_at(zip, end + 12) as usize;
+        let start = u32_at(zip, end + 16) as usize;
+        assert_eq!(start + size, end);
+
+        let mut parsed = Vec::new();
+        let mut at = start;
+
+        for _ in 0..count {
+            assert_eq!(u32_at(zip, at), 0x0201_4b50);
+            let crc = u32_at(zip, at + 16);
+            let size = u32_at(zip, at + 24) as usize;
+            assert_eq!(u32_at(zip, at + 20) as usize, size);
+            let name_length = u16_at(zip, at + 28) as usize;
+            let extra_length = u16_at(zip, at + 30) as usize;
+            let offset = u32_at(zip, at + 42) as usize;
+            let name = String::from_utf8(zip[at + 46..at + 46 + name_length].to_vec()).unwrap();
+
+            // the local header repeats the name, and the contents follow it
+            assert_eq!(u32_at(zip, offset), 0x0403_4b50);
+            assert_eq!(u16_at(zip, offset + 26) as usize, name_length);
+            assert_eq!(
+                &zip[offset + 30..offset + 30 + name_length],
+                name.as_bytes()
+            );
+            let data = offset + 30 + name_length + u16_at(zip, offset + 28) as usize;
+            let contents = zip[data..data + size].to_vec();
+
+            let descriptor = data + size;
+            assert_eq!(u32_at(zip, descriptor), 0x0807_4b50);
+            assert_eq!(u32_at(zip, descriptor + 4), crc);
+            assert_eq!(u32_at(zip, descriptor + 8) as usize, size);
+
+            parsed.push(Parsed {
+                name,
+                crc,
+                contents,
+            });
+            at += 46 + name_length + extra_length;
+        }
+
+        assert_eq!(at, end);
+        parsed
+    }
+
+    fn read_all(entries: Vec<Entry>) -> Vec<u8> {
+        let mut zip = Vec::new();
+        ZipArchive::new(entries).read_to_end(&mut zip).unwrap();
+        zip
+    }
+
+    #[test]
+    fn round_trips_a_tree() {
+        let base = std::env::temp_dir().join(random_string(12));
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("sub/empty")).unwrap();
+        fs::write(root.join("a.txt"), "hello").unwrap();
+        fs::write(root.join("sub/b.bin"), vec![7u8; 100_000]).unwrap();
+        fs::write(root.join("sub/empty.txt"), "").unwrap();
+
+        let entries = walk(&root, "tree", &|_: &Path| true).unwrap();
+        let parsed = parse(&read_all(entries));
+
+        let names = parsed
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "tree/",
+                "tree/a.txt",
+                "tree/sub/",
+                "tree/sub/b.bin",
+                "tree/sub/empty/",
+                "tree/sub/empty.txt",
+            ]
+        );
+
+        for entry in &parsed {
+            assert_eq!(entry.crc, crc(&entry.contents));
+
+            if !entry.name.ends_with('/') {
+                let on_disk = base.join(&entry.name);
+                assert_eq!(entry.contents, fs::read(on_disk).unwrap());
+            }
+        }
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn pads_files_which_shrink() {
+        let path = std::env::temp_dir().join(random_string(12));
+        fs::write(&path, "abc").unwrap();
+
+        let entries = vec![Entry {
+            path: path.clone(),
+            name: "file.txt".to_string(),
+            is_dir: false,
+            size: 6,
+            modified: 0,
+        }];
+        let parsed = parse(&read_all(entries));
+
+        assert_eq!(parsed[0].contents, b"abc\0\0\0");
+        assert_eq!(parsed[0].crc, crc(b"abc\0\0\0"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn clamps_dates_before_1980() {
+        assert_eq!(dos_date_time(0), (0, (1 << 5) | 1));
+        // 2000-01-02 03:04:06
+        assert_eq!(
+            dos_date_time(946_782_246),
+            ((3 << 11) | (4 << 5) | 3, (20 << 9) | (1 << 5) | 2)
+        );
+    }
+}
@@ -1,11 +1,17 @@
 use std::io::prelude::*;
 use std::net::{TcpListener, TcpStream};
 use std::sync::Arc;
+use std::time::Duration;
 
 use clap::Clap;
 
-use http_impl_demo::request::{Get, Post, Patch, Delete, Request, RequestHandler};
-use http_impl_demo::{Opts, Response, ThreadPool};
+use http_impl_demo::request::{
+    Delete, Get, Head, Limits, Options, ParseError, Patch, Post, Request, RequestHandler,
+    RequestReader,
+};
+use http_impl_demo::{
+    Body, Cors, Opts, Response, StatusCode, ThreadPool, Visibility, HTTP_VERSION,
+};
 use std::io;
 
 fn main() {
@@ -22,7 +28,9 @@ fn main() {
         let stream = stream.unwrap();
 
         pool.execute(|opts| {
-            handle_connection(stream, opts).unwrap();
+            if let Err(err) = handle_connection(stream, opts) {
+                println!("ERR: {}", err);
+            }
         });
     }
 
@@ -30,40 +38,219 @@ fn main() {
 }
 
 fn handle_connection(mut stream: TcpStream, opts: Arc<Opts>) -> Result<(), io::Error> {
-    const BUFFER_SIZE: usize = 1_048_576; // 1MB
-    let mut buffer = [0; BUFFER_SIZE];
-
-    stream.read(&mut buffer)?;
-
-    let req = Request::from_bytes(&buffer);
-
-    let response = match match req.status_line.method {
-        "GET" => Get::get_response(&req, opts),
-        "POST" => Post::get_response(&req, opts),
-        "PATCH" => Patch::get_response(&req, opts),
-        "DELETE" => Delete::get_response(&req, opts),
-        _ => Ok(Response::error(
-            501,
-            Some(format!("Method {} is not supported", req.status_line.method).as_str()),
-        )),
-    } {
-        Ok(res) => res,
-        Err(err) => {
-            println!("ERR: {}", err);
-            Response::error(500, Some(err.to_string().as_str()))
+    let limits = Limits::from(opts.as_ref());
+    let cors = Cors::from(opts.as_ref());
+    let mut reader = RequestReader::new(stream.try_clone()?, limits);
+
+    stream.set_write_timeout(Some(Duration::from_secs(opts.write_timeout)))?;
+
+    // serve requests in the order they arrive
+    // until either side asks to close the connection
+    while handle_request(&mut stream, &mut reader, limits, &cors, opts.clone())? {}
+
+    Ok(())
+}
+
+/// Reads and responds to a single request on the connection.
+///
+/// Returns whether the connection should be kept open for another request.
+fn handle_request(
+    stream: &mut TcpStream,
+    reader: &mut RequestReader,
+    limits: Limits,
+    cors: &Cors,
+    opts: Arc<Opts>,
+) -> Result<bool, io::Error> {
+    let head = match reader.read_head() {
+        Ok(Some(head)) => head,
+        Ok(None) => return Ok(false),
+        Err(err) => return reject(stream, err),
+    };
+
+    let mut req = match Request::from_bytes(&head, limits) {
+        Ok(req) => req,
+        Err(err) => return reject(stream, err),
+    };
+
+    if let Err(err) = reader.check_body(&req) {
+        return reject(stream, err);
+    }
+
+    // the ignore file is read once, and shared by everything handling the request
+    let visibility = Visibility::from(opts.as_ref());
+
+    if let Some(expect) = req.headers.get("Expect") {
+        let response = if expect.eq_ignore_ascii_case("100-continue") {
+            check_headers(&req, opts.clone(), &visibility)
+        } else {
+            Some(Response::error(
+                StatusCode::EXPECTATION_FAILED,
+                Some(format!("Expectation <code>{}</code> is not supported", expect).as_str()),
+            ))
+        };
+
+        if let Some(mut response) = response {
+            cors.apply(&req, &mut response);
+
+            if req.status_line.method == "HEAD" {
+                response.body = None;
+            }
+
+            // the body was never asked for, so the connection cannot be reused
+            response.headers.insert("Connection", "close");
+
+            response.write_to(stream)?;
+            stream.flush()?;
+
+            log(&req, &response);
+
+            return Ok(false);
+        }
+
+        // HTTP/1.0 clients do not understand interim responses
+        if req.status_line.http_version == HTTP_VERSION {
+            write!(
+                stream,
+                "{} {} {}\r\n\r\n",
+                HTTP_VERSION,
+                StatusCode::CONTINUE,
+                StatusCode::CONTINUE.reason_phrase()
+            )?;
+            stream.flush()?;
         }
+    }
+
+    let body = match reader.read_body(&req) {
+        Ok(body) => body,
+        Err(err) => return reject(stream, err),
     };
+    req.body = body.as_deref();
+
+    let mut keep_alive = req.keep_alive();
+
+    let mut response = get_response(&req, opts.clone(), &visibility);
+    cors.apply(&req, &mut response);
+
+    // responses to HEAD keep the headers describing the body, but never send it,
+    // including error responses which were built before reaching the handler
+    if req.status_line.method == "HEAD" {
+        response.body = None;
+    }
+
+    // HTTP/1.0 clients cannot decode chunked bodies,
+    // so the end of the body is marked by closing the connection instead
+    if req.status_line.http_version != HTTP_VERSION
+        && response.body.as_ref().is_some_and(Body::is_chunked)
+    {
+        response.headers.remove("Transfer-Encoding");
+        response.body = response.body.take().map(Body::unchunked);
+        keep_alive = false;
+    }
 
-    stream.write(response.to_bytes().as_slice())?;
+    if keep_alive {
+        response
+            .headers
+            .insert("Connection", "keep-alive".to_string());
+        response
+            .headers
+            .insert("Keep-Alive", format!("timeout={}", opts.keep_alive_timeout));
+    } else {
+        response.headers.insert("Connection", "close".to_string());
+    }
+
+    response.write_to(stream)?;
     stream.flush()?;
 
     log(&req, &response);
 
-    Ok(())
+    Ok(keep_alive)
+}
+
+/// Passes a request to the handler for its method
+fn get_response<'a>(
+    req: &'a Request<'a>,
+    opts: Arc<Opts>,
+    visibility: &Visibility,
+) -> Response<'a> {
+    // hidden paths are checked first, so that nothing reveals they exist
+    if let Some(response) = visibility.check(req) {
+        return response;
+    }
+
+    if let Some(response) = Options::check_method(req, opts.clone()) {
+        return response;
+    }
+
+    let response = match req.status_line.method {
+        "GET" => Get::get_response(req, opts, visibility),
+        "HEAD" => Head::get_response(req, opts, visibility),
+        "POST" => Post::get_response(req, opts, visibility),
+        "PATCH" => Patch::get_response(req, opts, visibility),
+        "DELETE" => Delete::get_response(req, opts, visibility),
+        "OPTIONS" => Options::get_response(req, opts, visibility),
+        _ => unreachable!("unknown methods are rejected by check_method"),
+    };
+
+    match response {
+        Ok(res) => res,
+        Err(err) => {
+            println!("ERR: {}", err);
+            Response::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(err.to_string().as_str()),
+            )
+        }
+    }
+}
+
+/// Checks whether a request can succeed before its body is read
+fn check_headers<'a>(
+    req: &'a Request<'a>,
+    opts: Arc<Opts>,
+    visibility: &Visibility,
+) -> Option<Response<'a>> {
+    if let Some(response) = visibility.check(req) {
+        return Some(response);
+    }
+
+    if let Some(response) = Options::check_method(req, opts.clone()) {
+        return Some(response);
+    }
+
+    match req.status_line.method {
+        "GET" => Get::check_headers(req, opts),
+        "HEAD" => Head::check_headers(req, opts),
+        "POST" => Post::check_headers(req, opts),
+        "PATCH" => Patch::check_headers(req, opts),
+        "DELETE" => Delete::check_headers(req, opts),
+        "OPTIONS" => Options::check_headers(req, opts),
+        _ => None,
+    }
+}
+
+/// Responds to a request which could not be read or parsed.
+///
+/// The rest of the stream cannot be trusted after this,
+/// so the connection is always closed.
+fn reject(stream: &mut TcpStream, err: ParseError) -> Result<bool, io::Error> {
+    match err {
+        ParseError::Io(err) => Err(err),
+        err => {
+            println!("ERR: {}", err);
+
+            let mut response = Response::error(err.status_code(), Some(err.to_string().as_str()));
+            response.headers.insert("Connection", "close".to_string());
+
+            response.write_to(stream)?;
+            stream.flush()?;
+
+            Ok(false)
+        }
+    }
 }
 
 fn log(req: &Request, res: &Response) {
-    let host = req.headers.get("Host").unwrap_or(&"?????");
+    let host = req.headers.get("Host").unwrap_or("?????");
     let date = res.headers.get("Date").unwrap();
     let method = req.status_line.method;
     let code = res.status_code;
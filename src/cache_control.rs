@@ -0,0 +1,70 @@
+use std::str::FromStr;
+
+use crate::glob;
+
+/// A rule giving the `Cache-Control` header for files matching a pattern,
+/// written as `<pattern>=<value>`, for example `*.js=max-age=31536000, immutable`.
+///
+/// Patterns without a `/` are matched against the file name,
+/// otherwise they are matched against the whole path from the served directory.
+/// A bare extension such as `.css` is short for `*.css`.
+#[derive(Debug, Clone)]
+pub struct CacheRule {
+    pub pattern: String,
+    pub value: String,
+}
+
+impl CacheRule {
+    /// Checks whether the rule applies to a path relative to the served directory
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+
+        if self.pattern.contains('/') {
+            glob::matches(self.pattern.trim_start_matches('/'), path)
+        } else {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            glob::matches(&self.pattern, name)
+        }
+    }
+
+    /// Gets the `Cache-Control` value from the first rule which applies to a path
+    pub fn find<'r>(rules: &'r [CacheRule], path: &str) -> Option<&'r str> {
+        rules
+            .iter()
+            .find(|rule| rule.matches(path))
+            .map(|rule| rule.value.as_str())
+    }
+}
+
+impl FromStr for CacheRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let (pattern, value) = rule.split_once('=').ok_or_else(|| {
+            format!(
+                "Cache rule `{}` should be written as <pattern>=<value>",
+                rule
+            )
+        })?;
+
+        let (pattern, value) = (pattern.trim(), value.trim());
+
+        if pattern.is_empty() || value.is_empty() {
+            return Err(format!(
+                "Cache rule `{}` is missing a pattern or value",
+                rule
+            ));
+        }
+
+        let pattern = if pattern.starts_with('.') && !pattern.contains(&['/', '*', '?', '['][..]) {
+            format!("*{}", pattern)
+        } else {
+            pattern.to_string()
+        };
+
+        Ok(CacheRule {
+            pattern,
+            value: value.to_string(),
+        })
+    }
+}
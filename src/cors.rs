@@ -0,0 +1,119 @@
+use crate::request::Request;
+use crate::{Opts, Response};
+
+/// Cross-origin resource sharing policy,
+/// letting pages served from other origins use the server from a browser.
+///
+/// Nothing is shared unless at least one origin is allowed.
+#[derive(Debug, Clone)]
+pub struct Cors {
+    /// Origins allowed to make requests, or `*` for any origin
+    pub origins: Vec<String>,
+    /// Methods allowed in cross-origin requests
+    pub methods: Vec<String>,
+    /// Request headers allowed in cross-origin requests, or `*` for any header
+    pub headers: Vec<String>,
+    /// Whether cookies and authorization may be sent with requests
+    pub credentials: bool,
+    /// Seconds a browser may cache the result of a preflight request
+    pub max_age: Option<u64>,
+}
+
+impl Cors {
+    /// Checks whether a request is a CORS preflight,
+    /// which asks permission before sending the actual request.
+    pub fn is_preflight(req: &Request) -> bool {
+        req.status_line.method == "OPTIONS"
+            && req.headers.contains_key("Origin")
+            && req.headers.contains_key("Access-Control-Request-Method")
+    }
+
+    /// Adds CORS headers to a response
+    /// if the request came from an allowed origin.
+    ///
+    /// Responses to preflight requests are also told
+    /// which methods and headers may be used.
+    pub fn apply(&self, req: &Request, response: &mut Response) {
+        if self.origins.is_empty() {
+            return;
+        }
+
+        let any_origin = self.origins.iter().any(|origin| origin == "*");
+
+        // the allowed origin depends on the request,
+        // so caches must not share the response between origins
+        if !any_origin || self.credentials {
+            response.headers.append("Vary", "Origin");
+        }
+
+        let origin = match req.headers.get("Origin") {
+            Some(origin) if any_origin || self.is_origin_allowed(origin) => origin,
+            _ => return,
+        };
+
+        // browsers refuse a wildcard origin when credentials are included
+        if any_origin && !self.credentials {
+            response.headers.insert("Access-Control-Allow-Origin", "*");
+        } else {
+            response
+                .headers
+                .insert("Access-Control-Allow-Origin", origin);
+        }
+
+        if self.credentials {
+            response
+                .headers
+                .insert("Access-Control-Allow-Credentials", "true");
+        }
+
+        if !Cors::is_preflight(req) {
+            return;
+        }
+
+        response
+            .headers
+            .insert("Access-Control-Allow-Methods", self.methods.join(", "));
+
+        let any_header = self.headers.iter().any(|header| header == "*");
+        if any_header && self.credentials {
+            // a wildcard is taken literally when credentials are included,
+            // so allow exactly the headers which were asked for
+            if let Some(requested) = req.headers.get("Access-Control-Request-Headers") {
+                response
+                    .headers
+                    .insert("Access-Control-Allow-Headers", requested);
+            }
+        } else if !self.headers.is_empty() {
+            response
+                .headers
+                .insert("Access-Control-Allow-Headers", self.headers.join(", "));
+        }
+
+        if let Some(max_age) = self.max_age {
+            response
+                .headers
+                .insert("Access-Control-Max-Age", max_age.to_string());
+        }
+    }
+
+    /// Checks whether an origin is in the list of allowed origins.
+    ///
+    /// Origins are compared without regard to case.
+    fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+    }
+}
+
+impl From<&Opts> for Cors {
+    fn from(opts: &Opts) -> Self {
+        Cors {
+            origins: opts.cors_origin.clone(),
+            methods: opts.cors_methods.clone(),
+            headers: opts.cors_headers.clone(),
+            credentials: opts.cors_credentials,
+            max_age: opts.cors_max_age,
+        }
+    }
+}
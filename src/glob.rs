@@ -0,0 +1,215 @@
+/// Checks whether a path matches a glob pattern.
+///
+/// - `*` matches any run of characters within a single path segment
+/// - `**` matches any run of characters, including across segments,
+///   and `**/` may also match no segments at all
+/// - `?` matches any single character other than `/`
+/// - `[abc]` and `[a-z]` match a single character from a set,
+///   which is negated if it starts with `!` or `^`
+/// - `\` matches the character after it literally
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let tokens = tokenize(pattern);
+    let path = path.chars().collect::<Vec<_>>();
+
+    // Works backwards through the pattern,
+    // where `rest[i]` is whether the tokens already seen match `path[i..]`.
+    // This takes time proportional to the pattern length times the path length,
+    // however many wildcards the pattern contains.
+    let mut rest = vec![false; path.len() + 1];
+    rest[path.len()] = true;
+
+    for token in tokens.iter().rev() {
+        let mut current = vec![false; path.len() + 1];
+        // whether a `/` at or after `i` is followed by a match of the rest
+        let mut slash_then_rest = false;
+
+        for i in (0..=path.len()).rev() {
+            let c = path.get(i).copied();
+
+            current[i] = match token {
+                Token::Literal(literal) => c == Some(*literal) && rest[i + 1],
+                Token::Any => c.is_some_and(|c| c != '/') && rest[i + 1],
+                Token::Class(class) => {
+                    c.is_some_and(|c| c != '/' && class.contains(c)) && rest[i + 1]
+                }
+                Token::Star => rest[i] || (c.is_some_and(|c| c != '/') && current[i + 1]),
+                Token::DoubleStar => rest[i] || (c.is_some() && current[i + 1]),
+                Token::DoubleStarSlash => {
+                    if c == Some('/') && rest[i + 1] {
+                        slash_then_rest = true;
+                    }
+
+                    rest[i] || slash_then_rest
+                }
+            };
+        }
+
+        rest = current;
+    }
+
+    rest[0]
+}
+
+/// A single element of a glob pattern
+enum Token {
+    Literal(char),
+    /// `?`
+    Any,
+    Class(Class),
+    /// `*`
+    Star,
+    /// `**`
+    DoubleStar,
+    /// `**/`, which also matches nothing at all
+    DoubleStarSlash,
+}
+
+/// Splits a pattern into the elements it is made up of
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < pattern.len() {
+        let (token, len) = match pattern[i] {
+            '*' if pattern.get(i + 1) == Some(&'*') => {
+                if pattern.get(i + 2) == Some(&'/') {
+                    (Token::DoubleStarSlash, 3)
+                } else {
+                    (Token::DoubleStar, 2)
+                }
+            }
+            '*' => (Token::Star, 1),
+            '?' => (Token::Any, 1),
+            '[' => match parse_class(&pattern[i..]) {
+                Some((class, len)) => (Token::Class(class), len),
+                // an unclosed bracket is just a bracket
+                None => (Token::Literal('['), 1),
+            },
+            '\\' if i + 1 < pattern.len() => (Token::Literal(pattern[i + 1]), 2),
+            c => (Token::Literal(c), 1),
+        };
+
+        tokens.push(token);
+        i += len;
+    }
+
+    tokens
+}
+
+/// A set of characters in brackets
+struct Class {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl Class {
+    fn contains(&self, c: char) -> bool {
+        let found = self
+            .ranges
+            .iter()
+            .any(|&(start, end)| start <= c && c <= end);
+
+        found != self.negated
+    }
+}
+
+/// Parses a bracketed character set at the start of a pattern,
+/// returning it along with the number of pattern characters it took up.
+fn parse_class(pattern: &[char]) -> Option<(Class, usize)> {
+    let mut i = 1;
+
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+
+    loop {
+        let c = *pattern.get(i)?;
+
+        // a bracket straight after the opening one is part of the set
+        if c == ']' && !first {
+            return Some((Class { negated, ranges }, i + 1));
+        }
+
+        first = false;
+
+        match (pattern.get(i + 1), pattern.get(i + 2)) {
+            (Some('-'), Some(&end)) if end != ']' => {
+                ranges.push((c, end));
+                i += 3;
+            }
+            _ => {
+                ranges.push((c, c));
+                i += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_literals_and_single_characters() {
+        assert!(matches("a.txt", "a.txt"));
+        assert!(!matches("a.txt", "b.txt"));
+        assert!(matches("?.txt", "a.txt"));
+        assert!(!matches("?", "/"));
+        assert!(!matches("a", ""));
+        assert!(matches("", ""));
+    }
+
+    #[test]
+    fn star_stays_within_a_segment() {
+        assert!(matches("*.log", "debug.log"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "a-b-b-c"));
+        assert!(!matches("*.log", "logs/debug.log"));
+        assert!(matches("logs/*", "logs/debug.log"));
+        assert!(!matches("logs/*", "logs/old/debug.log"));
+    }
+
+    #[test]
+    fn double_star_crosses_segments() {
+        assert!(matches("**", "a/b/c"));
+        assert!(matches("logs/**", "logs/old/debug.log"));
+        assert!(matches("**.log", "logs/old/debug.log"));
+        assert!(matches("**/debug.log", "debug.log"));
+        assert!(matches("**/debug.log", "logs/old/debug.log"));
+        assert!(!matches("**/debug.log", "logs/olddebug.log"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+    }
+
+    #[test]
+    fn matches_classes() {
+        assert!(matches("[abc].txt", "b.txt"));
+        assert!(!matches("[abc].txt", "d.txt"));
+        assert!(matches("file[0-9]", "file7"));
+        assert!(!matches("file[!0-9]", "file7"));
+        assert!(matches("file[^0-9]", "filex"));
+        assert!(matches("[]]", "]"));
+        assert!(!matches("[/]", "/"));
+        assert!(matches("a[", "a["));
+    }
+
+    #[test]
+    fn matches_escapes() {
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "a"));
+        assert!(matches("\\[a]", "[a]"));
+        assert!(matches("a\\", "a\\"));
+    }
+
+    #[test]
+    fn many_wildcards_finish_quickly() {
+        let name = "a".repeat(200);
+        assert!(!matches("*a*a*a*a*a*a*a*a*b", name.as_str()));
+        assert!(!matches(&"**a".repeat(50), &"a".repeat(49)));
+    }
+}
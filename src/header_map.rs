@@ -0,0 +1,70 @@
+/// An ordered collection of HTTP headers.
+///
+/// Header names are compared case-insensitively,
+/// and a name can hold several values,
+/// which are kept in the order they were added.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> HeaderMap {
+        HeaderMap {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Gets the first value for a header
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Gets every value for a header, in the order they were added
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.entries
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Checks whether at least one value exists for a header
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Adds a value for a header, keeping any existing values
+    pub fn append<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        self.entries.push((name.into(), value.into()));
+    }
+
+    /// Sets the value for a header, replacing any existing values
+    pub fn insert<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        let name = name.into();
+        self.remove(&name);
+        self.entries.push((name, value.into()));
+    }
+
+    /// Removes every value for a header
+    pub fn remove(&mut self, name: &str) {
+        self.entries.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+    }
+
+    /// Iterates over every header name and value, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -1,14 +1,30 @@
 use clap::{AppSettings, Clap};
 
-pub use response::Response;
+pub use cache_control::CacheRule;
+pub use cors::Cors;
+pub use header_map::HeaderMap;
+pub use listing::Listing;
+pub use response::{Body, Response, ResponseBuilder};
+pub use status_code::StatusCode;
 pub use thread_pool::ThreadPool;
+pub use uri::Uri;
+pub use visibility::{SymlinkPolicy, Visibility};
 
 mod thread_pool;
 pub mod request;
 mod response;
+mod status_code;
 pub mod buffer_utils;
+mod header_map;
+mod cors;
+mod cache_control;
+pub mod archive;
+pub mod glob;
+mod listing;
 pub mod multipart;
 pub mod path_utils;
+mod uri;
+mod visibility;
 
 pub const ERROR_PATH: &str = "errors";
 pub const HTTP_VERSION: &str = "HTTP/1.1";
@@ -23,6 +39,96 @@ pub struct Opts {
     #[clap(short, long, default_value = "127.0.0.1")]
     pub host: String,
 
+    /// Seconds to keep an idle connection open waiting for another request
+    #[clap(long, default_value = "5")]
+    pub keep_alive_timeout: u64,
+
+    /// Seconds allowed for a client to send the request line and headers
+    #[clap(long, default_value = "10")]
+    pub header_timeout: u64,
+
+    /// Seconds to wait for each part of a request body to arrive
+    #[clap(long, default_value = "30")]
+    pub body_timeout: u64,
+
+    /// Minimum average speed of a request body, in bytes per second,
+    /// once the body timeout has passed. Unlimited if not set
+    #[clap(long)]
+    pub min_body_rate: Option<u64>,
+
+    /// Seconds to wait for the client to accept each part of a response
+    #[clap(long, default_value = "30")]
+    pub write_timeout: u64,
+
+    /// Maximum size of the request line and headers, in bytes
+    #[clap(long, default_value = "65536")]
+    pub max_header_bytes: usize,
+
+    /// Maximum number of request headers
+    #[clap(long, default_value = "100")]
+    pub max_header_count: usize,
+
+    /// Maximum length of the request URI, in bytes
+    #[clap(long, default_value = "8192")]
+    pub max_uri_length: usize,
+
+    /// Maximum size of a request body, in bytes. Unlimited if not set
+    #[clap(long)]
+    pub max_body_size: Option<usize>,
+
+    /// Origin allowed to make cross-origin requests, or `*` for any origin.
+    /// Can be given more than once. CORS is disabled if not set
+    #[clap(long, number_of_values = 1)]
+    pub cors_origin: Vec<String>,
+
+    /// Comma-separated methods allowed in cross-origin requests
+    #[clap(long, use_delimiter = true, default_value = "GET,HEAD,POST,PATCH,DELETE")]
+    pub cors_methods: Vec<String>,
+
+    /// Comma-separated headers allowed in cross-origin requests, or `*` for any header
+    #[clap(long, use_delimiter = true, default_value = "Content-Type,X-File-Name")]
+    pub cors_headers: Vec<String>,
+
+    /// Allow cookies and authorization to be sent with cross-origin requests
+    #[clap(long)]
+    pub cors_credentials: bool,
+
+    /// Seconds a browser may cache the result of a CORS preflight request
+    #[clap(long)]
+    pub cors_max_age: Option<u64>,
+
+    /// Cache-Control header for files matching a pattern, written as <pattern>=<value>.
+    /// Can be given more than once, and the first matching rule is used
+    #[clap(long, number_of_values = 1)]
+    pub cache_control: Vec<CacheRule>,
+
+    /// Comma-separated file names to serve for a directory, in order of preference
+    #[clap(long, use_delimiter = true, default_value = "index.html")]
+    pub index: Vec<String>,
+
+    /// Respond with 403 Forbidden to directories without an index file,
+    /// rather than listing or archiving their contents
+    #[clap(long)]
+    pub no_listing: bool,
+
+    /// Cache-Control header for directory listings
+    #[clap(long, default_value = "no-store")]
+    pub listing_cache_control: String,
+
+    /// Serve files and directories whose names start with a dot
+    #[clap(long)]
+    pub show_hidden: bool,
+
+    /// Which symlinks to follow: follow, within-root to only follow those
+    /// which point inside the directory, or never
+    #[clap(long, default_value = "within-root")]
+    pub symlinks: SymlinkPolicy,
+
+    /// File to serve, relative to the directory, for paths which do not exist
+    /// and do not look like files. Lets single-page apps handle their own routes
+    #[clap(long)]
+    pub spa: Option<String>,
+
     #[clap(default_value = ".")]
     pub directory: String,
 }
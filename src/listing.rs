@@ -0,0 +1,469 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use urlencoding::encode;
+
+use crate::{glob, Response, Uri, Visibility};
+
+/// A file or directory shown in a directory listing
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+    /// Size in bytes, which is always 0 for directories
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// Guessed MIME type, which is `None` for directories
+    pub mime: Option<String>,
+}
+
+/// Whether an entry is a file or a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryType {
+    File,
+    Directory,
+}
+
+impl EntryType {
+    fn parse(entry_type: &str) -> Option<EntryType> {
+        match entry_type {
+            "file" => Some(EntryType::File),
+            "dir" | "directory" => Some(EntryType::Directory),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            EntryType::File => "file",
+            EntryType::Directory => "directory",
+        }
+    }
+}
+
+/// Column a listing is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Mtime,
+    Mime,
+}
+
+impl SortKey {
+    const ALL: [SortKey; 4] = [SortKey::Name, SortKey::Size, SortKey::Mtime, SortKey::Mime];
+
+    fn parse(key: &str) -> Option<SortKey> {
+        match key {
+            "name" => Some(SortKey::Name),
+            "size" => Some(SortKey::Size),
+            "mtime" => Some(SortKey::Mtime),
+            "mime" | "type" => Some(SortKey::Mime),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Mtime => "mtime",
+            SortKey::Mime => "mime",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            SortKey::Name => "Name",
+            SortKey::Size => "Size",
+            SortKey::Mtime => "Modified",
+            SortKey::Mime => "Type",
+        }
+    }
+}
+
+/// The contents of a directory, filtered and sorted for display.
+///
+/// Directories always come before files,
+/// whichever column the listing is sorted by.
+pub struct Listing {
+    /// Decoded request path of the directory, ending in `/`
+    pub path: String,
+    pub entries: Vec<Entry>,
+    pub sort: SortKey,
+    pub descending: bool,
+    /// Glob which entry names must match
+    pub filter: Option<String>,
+    /// The only type of entry to show
+    pub entry_type: Option<EntryType>,
+}
+
+impl Entry {
+    pub fn entry_type(&self) -> EntryType {
+        if self.is_dir {
+            EntryType::Directory
+        } else {
+            EntryType::File
+        }
+    }
+}
+
+impl Listing {
+    /// Reads the contents of a directory,
+    /// filtered according to the `filter` and `type` query parameters
+    /// and sorted according to the `sort` and `order` query parameters.
+    ///
+    /// Hidden entries, and those whose metadata cannot be read, are left out.
+    pub fn read(dir: &Path, uri: &Uri, visibility: &Visibility) -> io::Result<Listing> {
+        let filter = uri
+            .query_param("filter")
+            .filter(|filter| !filter.is_empty())
+            .map(str::to_string);
+        let entry_type = uri.query_param("type").and_then(EntryType::parse);
+
+        let mut entries = Vec::new();
+
+        for entry in dir.read_dir()? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if visibility.is_hidden(&path) {
+                continue;
+            }
+
+            let metadata = match fs::metadata(&path).or_else(|_| entry.metadata()) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let is_dir = metadata.is_dir();
+
+            if let Some(filter) = &filter {
+                if !glob::matches(filter, name.as_str()) {
+                    continue;
+                }
+            }
+
+            if entry_type.is_some_and(|entry_type| (entry_type == EntryType::Directory) != is_dir) {
+                continue;
+            }
+
+            entries.push(Entry {
+                is_dir,
+                size: if is_dir { 0 } else { metadata.len() },
+                modified: metadata.modified().ok().map(DateTime::from),
+                mime: if is_dir {
+                    None
+                } else {
+                    Some(Response::guess_content_type(name.as_str()))
+                },
+                name,
+            });
+        }
+
+        let sort = uri
+            .query_param("sort")
+            .and_then(SortKey::parse)
+            .unwrap_or(SortKey::Name);
+        let descending = uri.query_param("order") == Some("desc");
+
+        let mut path = uri.path.clone();
+        if !path.ends_with('/') {
+            path.push('/');
+        }
+
+        let mut listing = Listing {
+            path,
+            entries,
+            sort,
+            descending,
+            filter,
+            entry_type,
+        };
+        listing.sort();
+
+        Ok(listing)
+    }
+
+    fn sort(&mut self) {
+        let (sort, descending) = (self.sort, self.descending);
+
+        self.entries.sort_by(|a, b| {
+            let by_key = match sort {
+                SortKey::Name => Ordering::Equal,
+                SortKey::Size => a.size.cmp(&b.size),
+                SortKey::Mtime => a.modified.cmp(&b.modified),
+                SortKey::Mime => a.mime.cmp(&b.mime),
+            };
+
+            let by_name = a
+                .name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.name.cmp(&b.name));
+
+            let order = by_key.then(by_name);
+
+            b.is_dir
+                .cmp(&a.is_dir)
+                .then(if descending { order.reverse() } else { order })
+        });
+    }
+
+    /// Gets the percent-encoded URL of the listed directory, ending in `/`
+    pub fn url(&self) -> String {
+        encode_path(self.path.as_str())
+    }
+
+    /// Gets the percent-encoded URL of the directory above the listed one,
+    /// or `None` at the root
+    pub fn parent_url(&self) -> Option<String> {
+        let trimmed = self.path.trim_end_matches('/');
+        let (parent, _) = trimmed.rsplit_once('/')?;
+
+        Some(format!("{}/", encode_path(parent)))
+    }
+
+    /// Gets the percent-encoded URL of an entry,
+    /// with a trailing `/` for directories
+    pub fn entry_url(&self, entry: &Entry) -> String {
+        let mut url = format!("{}{}", self.url(), encode(entry.name.as_str()));
+        if entry.is_dir {
+            url.push('/');
+        }
+
+        url
+    }
+
+    /// Builds the query string for the listing sorted another way,
+    /// keeping the current filters
+    fn query(&self, sort: SortKey, descending: bool) -> String {
+        let mut query = format!(
+            "?sort={}&order={}",
+            sort.as_str(),
+            if descending { "desc" } else { "asc" }
+        );
+
+        if let Some(filter) = &self.filter {
+            query.push_str(format!("&filter={}", encode(filter)).as_str());
+        }
+
+        if let Some(entry_type) = self.entry_type {
+            query.push_str(format!("&type={}", entry_type.as_str()).as_str());
+        }
+
+        query
+    }
+
+    /// Renders the listing as an HTML page
+    pub fn to_html(&self) -> String {
+        let headings = SortKey::ALL
+            .iter()
+            .map(|&key| {
+                // clicking the current column again flips the order
+                let descending = key == self.sort && !self.descending;
+                let arrow = match (key == self.sort, self.descending) {
+                    (false, _) => "",
+                    (true, false) => " &#9650;",
+                    (true, true) => " &#9660;",
+                };
+
+                format!(
+                    "<th><a href=\"{}\">{}</a>{}</th>",
+                    escape_html(self.query(key, descending).as_str()),
+                    key.title(),
+                    arrow
+                )
+            })
+            .collect::<String>();
+
+        let parent = match self.parent_url() {
+            Some(url) => format!(
+                "<tr><td><a href=\"{}\">../</a></td><td></td><td></td><td></td></tr>",
+                escape_html(url.as_str())
+            ),
+            None => String::new(),
+        };
+
+        let rows = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "<tr><td><a href=\"{url}\">{name}{slash}</a></td>\
+                    <td>{size}</td><td>{modified}</td><td>{mime}</td></tr>",
+                    url = escape_html(self.entry_url(entry).as_str()),
+                    name = escape_html(entry.name.as_str()),
+                    slash = if entry.is_dir { "/" } else { "" },
+                    size = if entry.is_dir {
+                        "-".to_string()
+                    } else {
+                        format_size(entry.size)
+                    },
+                    modified = entry.modified.map_or("-".to_string(), |modified| {
+                        modified.format("%Y-%m-%d %H:%M").to_string()
+                    }),
+                    mime = entry.mime.as_deref().map_or("-".to_string(), escape_html),
+                )
+            })
+            .collect::<String>();
+
+        format!(
+            "<!DOCTYPE html> \
+            <html lang=\"en\"> \
+            <head> \
+                <meta charset=\"UTF-8\"> \
+                <title>Index of {title}</title> \
+                <style> \
+                    body {{ font-family: sans-serif; }} \
+                    table {{ border-collapse: collapse; }} \
+                    th, td {{ padding: 0.2em 1em; text-align: left; }} \
+                    tbody tr:nth-child(odd) {{ background: #f4f4f4; }} \
+                </style> \
+            </head> \
+            <body> \
+                <h1>Index of {breadcrumbs}</h1> \
+                <p>Download as <a href=\"?download=zip\">zip</a> \
+                or <a href=\"?download=tar.gz\">tar.gz</a></p> \
+                <form> \
+                    <input name=\"filter\" placeholder=\"Filter, e.g. *.txt\" value=\"{filter}\"> \
+                    <input type=\"hidden\" name=\"sort\" value=\"{sort}\"> \
+                    <input type=\"hidden\" name=\"order\" value=\"{order}\"> \
+                    {entry_type} \
+                </form> \
+                <table> \
+                    <thead><tr>{headings}</tr></thead> \
+                    <tbody>{parent}{rows}</tbody> \
+                </table> \
+            </body> \
+            </html>",
+            title = escape_html(self.path.as_str()),
+            breadcrumbs = self.breadcrumbs(),
+            filter = escape_html(self.filter.as_deref().unwrap_or("")),
+            sort = self.sort.as_str(),
+            order = if self.descending { "desc" } else { "asc" },
+            entry_type = self.entry_type.map_or(String::new(), |entry_type| {
+                format!(
+                    "<input type=\"hidden\" name=\"type\" value=\"{}\">",
+                    entry_type.as_str()
+                )
+            }),
+            headings = headings,
+            parent = parent,
+            rows = rows,
+        )
+    }
+
+    /// Renders the listing as a JSON document.
+    ///
+    /// Sizes and MIME types are `null` for directories,
+    /// and modification times are given in RFC 3339 format.
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{{\"name\":{},\"type\":\"{}\",\"size\":{},\"mtime\":{},\"mime\":{},\"url\":{}}}",
+                    escape_json(entry.name.as_str()),
+                    entry.entry_type().as_str(),
+                    if entry.is_dir {
+                        "null".to_string()
+                    } else {
+                        entry.size.to_string()
+                    },
+                    entry.modified.map_or("null".to_string(), |modified| {
+                        escape_json(modified.to_rfc3339().as_str())
+                    }),
+                    entry.mime.as_deref().map_or("null".to_string(), escape_json),
+                    escape_json(self.entry_url(entry).as_str()),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            "{{\"path\":{},\"sort\":\"{}\",\"order\":\"{}\",\"entries\":[{}]}}",
+            escape_json(self.path.as_str()),
+            self.sort.as_str(),
+            if self.descending { "desc" } else { "asc" },
+            entries
+        )
+    }
+
+    /// Renders links to each directory above the listed one
+    fn breadcrumbs(&self) -> String {
+        let mut url = String::from("/");
+        let mut crumbs = vec!["<a href=\"/\">/</a>".to_string()];
+
+        for segment in self.path.split('/').filter(|segment| !segment.is_empty()) {
+            url.push_str(encode(segment).as_str());
+            url.push('/');
+
+            crumbs.push(format!(
+                "<a href=\"{}\">{}/</a>",
+                escape_html(url.as_str()),
+                escape_html(segment)
+            ));
+        }
+
+        crumbs.join("")
+    }
+}
+
+/// Percent-encodes each segment of a decoded path, keeping the `/` between them
+fn encode_path(path: &str) -> String {
+    path.split('/').map(encode).collect::<Vec<_>>().join("/")
+}
+
+/// Escapes text so that it can be placed in HTML content or attributes
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Quotes and escapes text as a JSON string
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+/// Formats a size in bytes using binary units
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, UNITS[unit])
+}
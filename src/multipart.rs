@@ -13,7 +13,7 @@ pub fn parse<'a>(req: &'a Request, content_type: &'a ComplexHeader) -> Result<Ve
     let boundary = "--".to_owned() + base_boundary + "\r\n";
     let end_boundary = "--".to_owned() + base_boundary + "--\r\n";
 
-    let body = req.body.unwrap();
+    let body = req.body.ok_or("Malformed multipart request; missing body")?;
     let body = body.strip_prefix(boundary.as_bytes()).unwrap_or(body);
     let body = body.strip_suffix(end_boundary.as_bytes()).unwrap_or(body);
 
@@ -21,27 +21,28 @@ pub fn parse<'a>(req: &'a Request, content_type: &'a ComplexHeader) -> Result<Ve
         .iter()
         .map(|&part| {
             const PREDICATE: &[u8] = "\r\n\r\n".as_bytes();
-            let (headers, body) = part.split_at(find_substring(part, PREDICATE).unwrap());
+            let end_headers = find_substring(part, PREDICATE)
+                .ok_or("Malformed multipart request; missing end of part headers")?;
+            let (headers, body) = part.split_at(end_headers);
             let body = &body[PREDICATE.len()..];
-            (headers, body)
+            Ok((headers, body))
         })
-        .collect::<Vec<(&[u8], &[u8])>>();
+        .collect::<Result<Vec<(&[u8], &[u8])>, &str>>()?;
 
-    let files = parts
+    parts
         .into_iter()
         .map(|(headers, body)| {
-            let headers = Request::parse_headers(headers);
+            let headers = Request::parse_headers(headers)
+                .map_err(|_| "Malformed multipart request; invalid part headers")?;
             let disposition =
-                Request::parse_complex_header(headers.get("Content-Disposition").unwrap_or(&""));
+                Request::parse_complex_header(headers.get("Content-Disposition").unwrap_or(""));
 
             let filename = get_filename_or_fallback(disposition.extras.get("filename"));
 
-            UploadFile {
+            Ok(UploadFile {
                 name: filename,
                 body,
-            }
+            })
         })
-        .collect();
-
-    Ok(files)
+        .collect()
 }
\ No newline at end of file
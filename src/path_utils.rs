@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
@@ -33,7 +33,7 @@ pub fn sanitise(string: &str) -> String {
 ///
 /// This does not check if the path exists or perform any IO.
 /// The check simply looks if the last segment contains an extension.
-pub fn is_filepath(path: &PathBuf) -> bool {
+pub fn is_filepath(path: &Path) -> bool {
     let name = path.file_name();
     let stem = path.file_stem();
 
@@ -1,64 +1,271 @@
-use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use urlencoding::decode;
+use urlencoding::encode;
 
-use crate::path_utils::get_path;
-use crate::request::{Request, RequestHandler, ResponseResult};
-use crate::{Opts, Response};
+use crate::archive;
+use crate::path_utils::{get_path, is_filepath};
+use crate::request::{Ranges, Request, RequestHandler, ResponseResult, Validators};
+use crate::{CacheRule, Listing, Opts, Response, ResponseBuilder, StatusCode, Visibility};
 
 pub struct Get;
 
-impl RequestHandler for Get {
-    fn get_response<'a>(req: &'a Request, opts: Arc<Opts>) -> ResponseResult<'a> {
-        let path = get_path(
-            opts.directory.as_str(),
-            decode(req.status_line.uri).unwrap().as_str(),
-        );
-
-        // default to index.html for directories
-        let file_path = if path.is_dir() {
-            path.join("index.html")
+impl Get {
+    /// Serves a file, or just the parts of it asked for by a `Range` header,
+    /// along with validators the client can use to check its copy later.
+    ///
+    /// Clients whose copy is still current get `304 Not Modified` instead.
+    /// Ranges are only honoured for `GET` requests,
+    /// and are ignored if the header is malformed
+    /// or the file has changed since the client's `If-Range` validator.
+    fn file_response<'a>(req: &'a Request<'a>, path: PathBuf, opts: &Opts) -> ResponseResult<'a> {
+        let relative_path = path
+            .strip_prefix(opts.directory.as_str())
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .into_owned();
+        let cache_control = CacheRule::find(&opts.cache_control, relative_path.as_str());
+
+        let metadata = path.metadata()?;
+        let validators = Validators::from_metadata(&metadata)?;
+
+        let mut response = if validators.is_not_modified(req) {
+            Response::builder().status(StatusCode::NOT_MODIFIED).build()
         } else {
-            path.to_path_buf()
+            let range = req
+                .headers
+                .get("Range")
+                .filter(|_| req.status_line.method == "GET")
+                .filter(|_| {
+                    req.headers
+                        .get("If-Range")
+                        .is_none_or(|if_range| validators.if_range_matches(if_range))
+                });
+
+            match range.and_then(|range| Ranges::parse(range, metadata.len())) {
+                Some(Ranges::Satisfiable(ranges)) if ranges.len() == 1 => {
+                    Response::file_range(path, ranges[0])?
+                }
+                Some(Ranges::Satisfiable(ranges)) => Response::file_ranges(path, &ranges)?,
+                Some(Ranges::Unsatisfiable) => Response::range_not_satisfiable(metadata.len()),
+                None => Response::file(path)?,
+            }
         };
 
-        if file_path.exists() {
-            let content = fs::read(&file_path)?;
-            Ok(Response::ok(200, file_path, content))
-        } else if path.is_dir() {
-            let dir_contents = path
-                .read_dir()?
-                .map(|file| file.unwrap().file_name())
-                .collect::<Vec<_>>();
-
-            let contents_html = dir_contents
-                .iter()
-                .map(|file| {
-                    format!(
-                        "<li><a href=\"{}\"</a>{}</li>",
-                        get_path(req.status_line.uri, file.to_str().unwrap())
-                            .to_str()
-                            .unwrap(),
-                        file.to_str().unwrap()
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join("");
-            Ok(Response::ok(
-                200,
-                path,
+        response.headers.insert("ETag", validators.etag.as_str());
+        response
+            .headers
+            .insert("Last-Modified", validators.last_modified_header());
+
+        if let Some(cache_control) = cache_control {
+            response.headers.insert("Cache-Control", cache_control);
+        }
+
+        Ok(response)
+    }
+
+    /// Streams an archive of a directory tree in the format named by `download`
+    fn archive_response<'a>(
+        req: &'a Request<'a>,
+        path: &Path,
+        download: &str,
+        visibility: &Visibility,
+    ) -> ResponseResult<'a> {
+        let format = match archive::Format::parse(download) {
+            Some(format) => format,
+            None => {
+                return Ok(Response::error(
+                    StatusCode::BAD_REQUEST,
+                    Some(
+                        format!(
+                            "Cannot download as <code>{}</code>, use zip or tar.gz",
+                            download
+                        )
+                        .as_str(),
+                    ),
+                ))
+            }
+        };
+
+        // the served directory itself is named after its location on disk
+        let name = req
+            .uri
+            .path
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .or_else(|| {
+                path.canonicalize()
+                    .ok()?
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "files".to_string());
+
+        let filename = format!("{}.{}", name, format.extension());
+
+        // non-ASCII names are only understood in the extended parameter
+        let fallback = filename
+            .chars()
+            .map(|c| {
+                if (c.is_ascii_graphic() || c == ' ') && c != '"' && c != '\\' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+
+        let builder = Response::builder()
+            .content_type(format.content_type())
+            .header(
+                "Content-Disposition",
                 format!(
-                    "<h1>Directory Listing</h1><ul><li><a href=\"{}\">..</a></li>{}</ul>",
-                    get_path(req.status_line.uri, "..").to_str().unwrap(),
-                    contents_html
-                )
-                .into_bytes(),
-            ))
+                    "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+                    fallback,
+                    encode(filename.as_str())
+                ),
+            )
+            .header("Cache-Control", "no-store");
+
+        if req.status_line.method == "HEAD" {
+            return Ok(Get::head_response(builder));
+        }
+
+        let entries = archive::walk(path, name.as_str(), &|path| !visibility.is_hidden(path))?;
+
+        Ok(builder.body(format.body(entries)).build())
+    }
+
+    /// Builds the response to a `HEAD` request for a body
+    /// whose length is only known once it has been generated,
+    /// so that the work is skipped and no length is given.
+    fn head_response<'a>(builder: ResponseBuilder) -> Response<'a> {
+        let mut response = builder.build();
+        response.headers.remove("Content-Length");
+
+        response
+    }
+
+    /// Finds the first index file in a directory which exists and is not hidden
+    fn index_file(dir: &Path, opts: &Opts, visibility: &Visibility) -> Option<PathBuf> {
+        opts.index
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file() && !visibility.is_hidden(path))
+    }
+
+    /// Responds with `403 Forbidden` for a directory whose contents may not be shown
+    fn listing_forbidden<'a>(req: &Request) -> Response<'a> {
+        Response::error(
+            StatusCode::FORBIDDEN,
+            Some(format!("Directory <code>{}</code> cannot be listed", req.uri.path).as_str()),
+        )
+    }
+
+    /// Gets the single-page app's fallback file for a path which does not exist.
+    ///
+    /// Paths with an extension are assumed to be missing assets,
+    /// which should still be reported as not found.
+    fn spa_fallback(path: &Path, opts: &Opts) -> Option<PathBuf> {
+        let fallback = get_path(opts.directory.as_str(), opts.spa.as_deref()?);
+
+        if is_filepath(path) || !fallback.is_file() {
+            return None;
+        }
+
+        Some(fallback)
+    }
+
+    /// Checks whether a directory listing should be sent as JSON rather than HTML,
+    /// either because of the `format` query parameter
+    /// or because the client accepts JSON.
+    fn wants_json(req: &Request) -> bool {
+        if let Some(format) = req.uri.query_param("format") {
+            return format.eq_ignore_ascii_case("json");
+        }
+
+        req.headers
+            .get_all("Accept")
+            .flat_map(|value| value.split(','))
+            .any(|media_range| {
+                let mut params = media_range.split(';').map(str::trim);
+                let media_type = params.next().unwrap_or("");
+
+                // a quality of zero means the type is not acceptable
+                let rejected = params.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q == 0.0)
+                });
+
+                media_type.eq_ignore_ascii_case("application/json") && !rejected
+            })
+    }
+}
+
+impl RequestHandler for Get {
+    fn get_response<'a>(
+        req: &'a Request<'a>,
+        opts: Arc<Opts>,
+        visibility: &Visibility,
+    ) -> ResponseResult<'a> {
+        let path = get_path(opts.directory.as_str(), req.uri.path.as_str());
+
+        if path.is_dir() {
+            // archives reveal as much as a listing does
+            if let Some(download) = req.uri.query_param("download") {
+                if opts.no_listing {
+                    return Ok(Get::listing_forbidden(req));
+                }
+
+                return Get::archive_response(req, &path, download, visibility);
+            }
+
+            if let Some(index) = Get::index_file(&path, &opts, visibility) {
+                return Get::file_response(req, index, &opts);
+            }
+
+            if opts.no_listing {
+                return Ok(Get::listing_forbidden(req));
+            }
+
+            let json = Get::wants_json(req);
+            let content_type = if json {
+                "application/json"
+            } else {
+                "text/html; charset=utf-8"
+            };
+
+            // listings change whenever a file is added or removed
+            let builder = Response::builder()
+                .content_type(content_type)
+                .header("Cache-Control", opts.listing_cache_control.as_str())
+                .header("Vary", "Accept");
+
+            if req.status_line.method == "HEAD" {
+                return Ok(Get::head_response(builder));
+            }
+
+            let listing = Listing::read(&path, &req.uri, visibility)?;
+            let body = if json {
+                listing.to_json()
+            } else {
+                listing.to_html()
+            };
+
+            Ok(builder.body(body.into_bytes()).build())
+        } else if path.exists() {
+            Get::file_response(req, path, &opts)
+        } else if let Some(fallback) = Get::spa_fallback(&path, &opts) {
+            Get::file_response(req, fallback, &opts)
         } else {
             Ok(Response::error(
-                404,
-                Some(format!("File <code>{}</code> does not exist", req.status_line.uri).as_str()),
+                StatusCode::NOT_FOUND,
+                Some(format!("File <code>{}</code> does not exist", req.uri.path).as_str()),
             ))
         }
     }
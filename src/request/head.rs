@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use crate::request::{Get, Request, RequestHandler, ResponseResult};
+use crate::{Opts, Visibility};
+
+pub struct Head;
+
+impl RequestHandler for Head {
+    /// Resolves the request exactly as `GET` would,
+    /// so the headers are identical.
+    ///
+    /// The body is dropped when the response is written,
+    /// and `GET` skips generating listings and archives for `HEAD` requests.
+    /// File bodies are streamed, so the file is opened but never read.
+    fn get_response<'a>(
+        req: &'a Request<'a>,
+        opts: Arc<Opts>,
+        visibility: &Visibility,
+    ) -> ResponseResult<'a> {
+        Get::get_response(req, opts, visibility)
+    }
+}
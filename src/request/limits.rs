@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use crate::Opts;
+
+/// Limits on the size of incoming requests
+/// and how long the server will wait for them.
+///
+/// Requests which exceed them are rejected while being read,
+/// before they reach a handler.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Maximum size of the status line and headers, in bytes
+    pub max_header_bytes: usize,
+    /// Maximum number of header lines
+    pub max_header_count: usize,
+    /// Maximum length of the request URI, in bytes
+    pub max_uri_length: usize,
+    /// Maximum size of the request body, in bytes
+    pub max_body_size: Option<usize>,
+    /// Time to wait for the next request on an idle connection
+    pub keep_alive_timeout: Duration,
+    /// Time allowed to receive the status line and headers
+    pub header_timeout: Duration,
+    /// Time to wait for each read of the body
+    pub body_timeout: Duration,
+    /// Minimum average rate of the body, in bytes per second
+    pub min_body_rate: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_header_bytes: 65_536,
+            max_header_count: 100,
+            max_uri_length: 8192,
+            max_body_size: None,
+            keep_alive_timeout: Duration::from_secs(5),
+            header_timeout: Duration::from_secs(10),
+            body_timeout: Duration::from_secs(30),
+            min_body_rate: None,
+        }
+    }
+}
+
+impl From<&Opts> for Limits {
+    fn from(opts: &Opts) -> Self {
+        Limits {
+            max_header_bytes: opts.max_header_bytes,
+            max_header_count: opts.max_header_count,
+            max_uri_length: opts.max_uri_length,
+            max_body_size: opts.max_body_size,
+            keep_alive_timeout: Duration::from_secs(opts.keep_alive_timeout),
+            header_timeout: Duration::from_secs(opts.header_timeout),
+            body_timeout: Duration::from_secs(opts.body_timeout),
+            min_body_rate: opts.min_body_rate,
+        }
+    }
+}
@@ -4,25 +4,53 @@ use std::error::Error;
 use std::sync::Arc;
 
 pub use get::Get;
+pub use head::Head;
 pub use patch::Patch;
 pub use post::Post;
 pub use delete::Delete;
+pub use limits::Limits;
+pub use options::{Options, METHODS};
+pub use parse_error::ParseError;
+pub use range::{ByteRange, Ranges};
+pub use reader::RequestReader;
+pub use validators::Validators;
 
 use crate::buffer_utils::{find_substring, split};
-use crate::{Opts, Response};
+use crate::{HeaderMap, Opts, Response, Uri, Visibility, HTTP_VERSION};
 
 mod get;
+mod head;
 mod patch;
 mod post;
 mod delete;
+mod limits;
+mod options;
+mod parse_error;
+mod range;
+mod reader;
+mod validators;
 
 pub type ResponseResult<'a> = Result<Response<'a>, Box<dyn Error>>;
 pub trait RequestHandler {
     /// Gets a response for a request.
     ///
     /// `Err` responses should only be given for uncaught server-side problems,
-    /// meaning HTTP error codes ie 400, 404, 501 etc are all `Ok` responses
-    fn get_response<'a>(req: &'a Request, opts: Arc<Opts>) -> ResponseResult<'a>;
+    /// meaning HTTP error codes ie 400, 404, 501 etc are all `Ok` responses.
+    /// `visibility` is built once per request, so the ignore file is only read once.
+    fn get_response<'a>(
+        req: &'a Request<'a>,
+        opts: Arc<Opts>,
+        visibility: &Visibility,
+    ) -> ResponseResult<'a>;
+
+    /// Checks whether a request can succeed using only its status line and headers.
+    ///
+    /// This is called before the body is read when a client sends `Expect: 100-continue`,
+    /// so that uploads which are bound to fail are rejected without being transferred.
+    /// Returns `None` if the request should go ahead.
+    fn check_headers<'a>(_req: &'a Request<'a>, _opts: Arc<Opts>) -> Option<Response<'a>> {
+        None
+    }
 }
 
 pub struct ReqStatusLine<'a> {
@@ -33,7 +61,8 @@ pub struct ReqStatusLine<'a> {
 
 pub struct Request<'a> {
     pub status_line: ReqStatusLine<'a>,
-    pub headers: HashMap<&'a str, &'a str>,
+    pub uri: Uri,
+    pub headers: HeaderMap,
     pub body: Option<&'a [u8]>,
 }
 
@@ -45,69 +74,132 @@ pub struct ComplexHeader<'a> {
 
 impl Request<'_> {
     /// Converts a raw request string into a `Request` struct
-    pub fn from_bytes(buffer: &[u8]) -> Request {
+    pub fn from_bytes(buffer: &[u8], limits: Limits) -> Result<Request<'_>, ParseError> {
         let buffer_size = buffer.len();
 
-        let end_status_line = find_substring(&buffer, b"\r\n").unwrap();
-        let status_line = &buffer[0..end_status_line];
+        let end_status_line = find_substring(buffer, b"\r\n").unwrap_or(buffer_size);
+        let status_line = Request::parse_status_line(&buffer[0..end_status_line], limits)?;
 
-        let end_headers = find_substring(&buffer, b"\r\n\r\n").unwrap_or(buffer_size);
-        let headers = Request::parse_headers(&buffer[end_status_line + 2..end_headers]);
+        let end_headers = find_substring(buffer, b"\r\n\r\n").unwrap_or(buffer_size);
+        let headers = if end_status_line < end_headers {
+            Request::parse_headers(&buffer[end_status_line + 2..end_headers])?
+        } else {
+            HeaderMap::new()
+        };
 
-        let content_length = headers.get("Content-Length").map_or(buffer_size, |&size| {
-            size.parse::<usize>().unwrap() + end_headers
-        });
+        if headers.len() > limits.max_header_count {
+            return Err(ParseError::HeadersTooLarge);
+        }
 
         const NEWLINE_OFFSET: usize = 4;
 
-        let body = if end_headers < buffer_size {
-            Some(
-                &buffer[end_headers + NEWLINE_OFFSET
-                    ..min(content_length + NEWLINE_OFFSET, buffer_size)],
-            )
+        let body_start = end_headers + NEWLINE_OFFSET;
+
+        let body_end = match headers.get("Content-Length") {
+            Some(size) => {
+                let size = size
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| ParseError::BadHeader)?;
+                min(body_start.saturating_add(size), buffer_size)
+            }
+            None => buffer_size,
+        };
+
+        let body = if body_start < body_end {
+            Some(&buffer[body_start..body_end])
         } else {
             None
         };
 
-        Request {
-            status_line: Request::parse_status_line(&status_line),
+        let uri = Uri::parse(status_line.uri).ok_or(ParseError::MalformedStatusLine)?;
+
+        Ok(Request {
+            status_line,
+            uri,
             headers,
             body,
+        })
+    }
+
+    /// Checks whether the client wants the connection kept open
+    /// after this request has been answered.
+    ///
+    /// HTTP/1.1 connections are persistent unless the client sends `Connection: close`,
+    /// whereas older versions must explicitly ask for `Connection: keep-alive`.
+    pub fn keep_alive(&self) -> bool {
+        let has_option = |option: &str| {
+            self.headers
+                .get_all("Connection")
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case(option))
+        };
+
+        if self.status_line.http_version == HTTP_VERSION {
+            !has_option("close")
+        } else {
+            has_option("keep-alive")
         }
     }
 
-    fn parse_status_line(status_line: &[u8]) -> ReqStatusLine {
-        let status_line = std::str::from_utf8(status_line).unwrap();
+    fn parse_status_line(
+        status_line: &[u8],
+        limits: Limits,
+    ) -> Result<ReqStatusLine<'_>, ParseError> {
+        let status_line =
+            std::str::from_utf8(status_line).map_err(|_| ParseError::MalformedStatusLine)?;
+
+        let mut parts = status_line.split(' ');
+
+        let (method, uri, http_version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(uri), Some(http_version))
+                if !method.is_empty() && !uri.is_empty() && parts.next().is_none() =>
+            {
+                (method, uri, http_version)
+            }
+            _ => return Err(ParseError::MalformedStatusLine),
+        };
+
+        if uri.len() > limits.max_uri_length {
+            return Err(ParseError::UriTooLong);
+        }
+
+        if !http_version.starts_with("HTTP/") {
+            return Err(ParseError::MalformedStatusLine);
+        }
 
-        let mut parts = status_line.split_whitespace();
-        let method = parts.next().unwrap();
-        let uri = parts.next().unwrap();
-        let http_version = parts.next().unwrap();
+        if http_version != "HTTP/1.1" && http_version != "HTTP/1.0" {
+            return Err(ParseError::UnsupportedVersion);
+        }
 
-        ReqStatusLine {
+        Ok(ReqStatusLine {
             method,
             uri,
             http_version,
-        }
+        })
     }
 
-    pub fn parse_headers(headers: &[u8]) -> HashMap<&str, &str> {
-        let mut map = HashMap::new();
+    pub fn parse_headers(headers: &[u8]) -> Result<HeaderMap, ParseError> {
+        let mut map = HeaderMap::new();
         for header in split(headers, b"\r\n") {
-            let header = std::str::from_utf8(header).unwrap();
+            let header = std::str::from_utf8(header).map_err(|_| ParseError::BadHeader)?;
+
+            let (key, value) = header.split_once(':').ok_or(ParseError::BadHeader)?;
+
+            // whitespace is not allowed between the field name and colon
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(ParseError::BadHeader);
+            }
 
-            let mut split = header.split(": ");
-            let key = split.next().unwrap();
-            let value = split.next().unwrap();
-            map.insert(key, value);
+            map.append(key, value.trim());
         }
 
-        map
+        Ok(map)
     }
 
     /// Splits a multi-part header that consists of
     /// semicolon-separated `key=value` pairs.
-    pub fn parse_complex_header(header: &str) -> ComplexHeader {
+    pub fn parse_complex_header(header: &str) -> ComplexHeader<'_> {
         let sections = header
             .split(";")
             .map(|section| section.trim())
@@ -116,7 +208,7 @@ impl Request<'_> {
         let value = sections[0];
 
         let extras = sections[1..]
-            .into_iter()
+            .iter()
             .map(|&part| {
                 let (key, value) = part.split_once("=").unwrap_or((part, ""));
 
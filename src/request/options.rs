@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::path_utils::get_path;
+use crate::request::{Request, RequestHandler, ResponseResult};
+use crate::{Opts, Response, StatusCode, Visibility};
+
+/// Every method the server knows how to handle
+pub const METHODS: [&str; 6] = ["GET", "HEAD", "POST", "PATCH", "DELETE", "OPTIONS"];
+
+pub struct Options;
+
+impl Options {
+    /// Gets the methods which can be used on the resource at a path.
+    ///
+    /// Files can be read, modified or deleted,
+    /// directories can be read or uploaded into,
+    /// and paths which do not exist yet can only be uploaded to.
+    pub fn allowed_methods(path: &Path) -> &'static [&'static str] {
+        if path.is_file() {
+            &["GET", "HEAD", "PATCH", "DELETE", "OPTIONS"]
+        } else if path.is_dir() {
+            &["GET", "HEAD", "POST", "PATCH", "OPTIONS"]
+        } else {
+            &["POST", "OPTIONS"]
+        }
+    }
+
+    /// Checks that a request's method is known
+    /// and can be used on the requested resource.
+    ///
+    /// Returns `None` if the request should go ahead.
+    pub fn check_method<'a>(req: &'a Request<'a>, opts: Arc<Opts>) -> Option<Response<'a>> {
+        let method = req.status_line.method;
+
+        if !METHODS.contains(&method) {
+            return Some(Response::error(
+                StatusCode::NOT_IMPLEMENTED,
+                Some(format!("Method {} is not supported", method).as_str()),
+            ));
+        }
+
+        let path = get_path(opts.directory.as_str(), req.uri.path.as_str());
+        let allowed = Options::allowed_methods(&path);
+
+        // missing resources are left to the handler to report as not found
+        if path.exists() && !allowed.contains(&method) {
+            let mut response = Response::error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                Some(
+                    format!(
+                        "Method {} cannot be used on <code>{}</code>",
+                        method, req.uri.path
+                    )
+                    .as_str(),
+                ),
+            );
+            response.headers.insert("Allow", allowed.join(", "));
+
+            return Some(response);
+        }
+
+        None
+    }
+}
+
+impl RequestHandler for Options {
+    fn get_response<'a>(
+        req: &'a Request<'a>,
+        opts: Arc<Opts>,
+        _visibility: &Visibility,
+    ) -> ResponseResult<'a> {
+        // `OPTIONS *` asks about the server as a whole
+        let allowed = if req.uri.path == "*" {
+            &METHODS
+        } else {
+            Options::allowed_methods(&get_path(opts.directory.as_str(), req.uri.path.as_str()))
+        };
+
+        Ok(Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .header("Allow", allowed.join(", "))
+            .build())
+    }
+}
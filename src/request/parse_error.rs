@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::StatusCode;
+
+/// Reasons a request could not be read or parsed.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The request line is missing or does not have a method, URI and version
+    MalformedStatusLine,
+    /// A header line is not valid UTF-8 or is missing its `:` separator
+    BadHeader,
+    /// The request body does not match the framing given by its headers
+    MalformedBody,
+    /// The request uses an HTTP version other than 1.0 or 1.1
+    UnsupportedVersion,
+    /// The request body uses a transfer coding other than `chunked`
+    UnsupportedTransferEncoding,
+    /// The request URI is longer than the server will accept
+    UriTooLong,
+    /// The status line and headers are larger than the server will accept
+    HeadersTooLarge,
+    /// The request body is larger than the server will accept
+    BodyTooLarge,
+    /// The client took too long to send the request
+    Timeout,
+    /// The underlying connection failed
+    Io(io::Error),
+}
+
+impl ParseError {
+    /// Gets the HTTP status code which should be sent back to the client
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ParseError::MalformedStatusLine => StatusCode::BAD_REQUEST,
+            ParseError::BadHeader => StatusCode::BAD_REQUEST,
+            ParseError::MalformedBody => StatusCode::BAD_REQUEST,
+            ParseError::UnsupportedVersion => StatusCode::HTTP_VERSION_NOT_SUPPORTED,
+            ParseError::UnsupportedTransferEncoding => StatusCode::NOT_IMPLEMENTED,
+            ParseError::UriTooLong => StatusCode::URI_TOO_LONG,
+            ParseError::HeadersTooLarge => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            ParseError::BodyTooLarge => StatusCode::CONTENT_TOO_LARGE,
+            ParseError::Timeout => StatusCode::REQUEST_TIMEOUT,
+            ParseError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MalformedStatusLine => write!(f, "Malformed request line"),
+            ParseError::BadHeader => write!(f, "Malformed request header"),
+            ParseError::MalformedBody => write!(f, "Malformed request body"),
+            ParseError::UnsupportedVersion => write!(f, "HTTP version is not supported"),
+            ParseError::UnsupportedTransferEncoding => {
+                write!(f, "Transfer-Encoding is not supported")
+            }
+            ParseError::UriTooLong => write!(f, "Request URI is too long"),
+            ParseError::HeadersTooLarge => write!(f, "Request headers are too large"),
+            ParseError::BodyTooLarge => write!(f, "Request body is too large"),
+            ParseError::Timeout => write!(f, "Timed out waiting for the request"),
+            ParseError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
+}
@@ -1,39 +1,47 @@
 use std::fs;
 use std::sync::Arc;
 
-use urlencoding::decode;
-
 use crate::path_utils::{get_path, is_filepath};
 use crate::request::{Request, RequestHandler, ResponseResult};
-use crate::{multipart, Opts, Response};
+use crate::{multipart, Opts, Response, StatusCode, Visibility};
 use std::path::PathBuf;
 
 pub struct Patch;
 
 impl RequestHandler for Patch {
-    fn get_response<'a>(req: &'a Request<'a>, opts: Arc<Opts>) -> ResponseResult {
+    fn get_response<'a>(
+        req: &'a Request<'a>,
+        opts: Arc<Opts>,
+        visibility: &Visibility,
+    ) -> ResponseResult<'a> {
+        if let Some(response) = Patch::check_headers(req, opts.clone()) {
+            return Ok(response);
+        }
+
         if req.body.is_none() {
-            return Ok(Response::error(400, Some("Missing request body")));
+            return Ok(Response::error(StatusCode::BAD_REQUEST, Some("Missing request body")));
         }
 
-        let save_path = get_path(
-            opts.directory.as_str(),
-            decode(req.status_line.uri).unwrap().as_str(),
-        );
+        let save_path = get_path(opts.directory.as_str(), req.uri.path.as_str());
 
         let is_filepath = is_filepath(&save_path);
 
         let content_type =
-            Request::parse_complex_header(req.headers.get("Content-Type").unwrap_or(&""));
+            Request::parse_complex_header(req.headers.get("Content-Type").unwrap_or(""));
 
         let mut file_paths = Vec::new();
 
         if content_type.value == "multipart/form-data" {
-            let files = multipart::parse(req, &content_type)?;
+            let files = match multipart::parse(req, &content_type) {
+                Ok(files) => files,
+                Err(message) => {
+                    return Ok(Response::error(StatusCode::BAD_REQUEST, Some(message)))
+                }
+            };
 
             if is_filepath && files.len() > 1 {
                 return Ok(Response::error(
-                    400,
+                    StatusCode::BAD_REQUEST,
                     Some(
                         format!(
                             "Path <code>{}</code> cannot be a filename",
@@ -45,11 +53,11 @@ impl RequestHandler for Patch {
             }
 
             if !save_path.exists() {
-                let requested_path = req.status_line.uri.parse::<PathBuf>()?;
+                let requested_path = req.uri.path.parse::<PathBuf>()?;
                 let rel_path = requested_path.parent().unwrap();
 
                 return Ok(Response::error(
-                    404,
+                    StatusCode::NOT_FOUND,
                     Some(
                         format!(
                             "Directory <code>{}</code> does not exist",
@@ -62,19 +70,29 @@ impl RequestHandler for Patch {
 
             for file in files {
                 let rel_path = get_path(
-                    req.status_line
-                        .uri
-                        .strip_prefix("/")
-                        .unwrap_or(req.status_line.uri),
+                    req.uri.path.strip_prefix('/').unwrap_or(&req.uri.path),
                     file.name.as_str(),
                 );
                 let file_path = save_path.join(file.name);
 
+                if visibility.is_hidden(&file_path) {
+                    return Ok(Response::error(
+                        StatusCode::NOT_FOUND,
+                        Some(
+                            format!(
+                                "File <code>{}</code> does not exist",
+                                rel_path.to_str().unwrap()
+                            )
+                            .as_str(),
+                        ),
+                    ));
+                }
+
                 if file_path.exists() {
                     fs::write(file_path, file.body)?;
                 } else {
                     return Ok(Response::error(
-                        404,
+                        StatusCode::NOT_FOUND,
                         Some(
                             format!(
                                 "File <code>{}</code> does not exist",
@@ -88,24 +106,7 @@ impl RequestHandler for Patch {
                 file_paths.push(rel_path);
             }
         } else {
-            let req_path = req
-                .status_line
-                .uri
-                .strip_prefix("/")
-                .unwrap_or(req.status_line.uri);
-
-            if !is_filepath {
-                return Ok(Response::error(
-                    400,
-                    Some(
-                        format!(
-                            "Path <code>{}</code> is not a filename",
-                            save_path.to_str().unwrap()
-                        )
-                        .as_str(),
-                    ),
-                ));
-            }
+            let req_path = req.uri.path.strip_prefix('/').unwrap_or(&req.uri.path);
 
             let rel_path = req_path.parse().unwrap();
 
@@ -120,17 +121,50 @@ impl RequestHandler for Patch {
             .map(|name| format!("http://{}/{}", host, name.to_str().unwrap()))
             .collect();
 
-        Ok(Response::ok(
-            201,
-            req.status_line.uri.parse()?,
-            (links.join("\n") + "\n").as_bytes().to_vec(),
-        ))
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .content_type("text/plain")
+            .body((links.join("\n") + "\n").into_bytes())
+            .build())
+    }
+
+    fn check_headers<'a>(req: &'a Request<'a>, opts: Arc<Opts>) -> Option<Response<'a>> {
+        let save_path = get_path(opts.directory.as_str(), req.uri.path.as_str());
+
+        let content_type =
+            Request::parse_complex_header(req.headers.get("Content-Type").unwrap_or(""));
+
+        if content_type.value == "multipart/form-data" {
+            return None;
+        }
+
+        if !is_filepath(&save_path) {
+            return Some(Response::error(
+                StatusCode::BAD_REQUEST,
+                Some(
+                    format!(
+                        "Path <code>{}</code> is not a filename",
+                        save_path.to_str().unwrap()
+                    )
+                    .as_str(),
+                ),
+            ));
+        }
+
+        if !save_path.is_file() {
+            return Some(Response::error(
+                StatusCode::NOT_FOUND,
+                Some(format!("File <code>{}</code> does not exist", req.uri.path).as_str()),
+            ));
+        }
+
+        None
     }
 }
 
 fn get_host(req: &Request, opts: Arc<Opts>) -> String {
     req.headers.get("Host").map_or_else(
         || format!("{}:{}", opts.host, opts.port),
-        |&host| host.to_string(),
+        |host| host.to_string(),
     )
 }
@@ -2,38 +2,47 @@ use std::fs;
 use std::sync::Arc;
 
 use mime_guess::get_mime_extensions_str;
-use urlencoding::{decode};
 
 use crate::path_utils::{get_path, is_filepath, random_string, sanitise};
 use crate::request::{Request, RequestHandler, ResponseResult};
-use crate::{multipart, Opts, Response};
+use crate::{multipart, Opts, Response, StatusCode, Visibility};
 
 pub struct Post;
 
 impl RequestHandler for Post {
-    fn get_response<'a>(req: &'a Request<'a>, opts: Arc<Opts>) -> ResponseResult {
+    fn get_response<'a>(
+        req: &'a Request<'a>,
+        opts: Arc<Opts>,
+        visibility: &Visibility,
+    ) -> ResponseResult<'a> {
+        if let Some(response) = Post::check_headers(req, opts.clone()) {
+            return Ok(response);
+        }
+
         if req.body.is_none() {
-            return Ok(Response::error(400, Some("Missing request body")));
+            return Ok(Response::error(StatusCode::BAD_REQUEST, Some("Missing request body")));
         }
 
-        let save_path = get_path(
-            opts.directory.as_str(),
-            decode(req.status_line.uri).unwrap().as_str(),
-        );
+        let save_path = get_path(opts.directory.as_str(), req.uri.path.as_str());
 
         let is_filepath = is_filepath(&save_path);
 
         let content_type =
-            Request::parse_complex_header(req.headers.get("Content-Type").unwrap_or(&""));
+            Request::parse_complex_header(req.headers.get("Content-Type").unwrap_or(""));
 
         let mut file_paths = Vec::new();
 
         if content_type.value == "multipart/form-data" {
-            let files = multipart::parse(req, &content_type)?;
+            let files = match multipart::parse(req, &content_type) {
+                Ok(files) => files,
+                Err(message) => {
+                    return Ok(Response::error(StatusCode::BAD_REQUEST, Some(message)))
+                }
+            };
 
             if is_filepath && files.len() > 1 {
                 return Ok(Response::error(
-                    400,
+                    StatusCode::BAD_REQUEST,
                     Some(
                         format!(
                             "Path <code>{}</code> cannot be a filename",
@@ -50,19 +59,29 @@ impl RequestHandler for Post {
 
             for file in files {
                 let rel_path = get_path(
-                    req.status_line
-                        .uri
-                        .strip_prefix("/")
-                        .unwrap_or(req.status_line.uri),
+                    req.uri.path.strip_prefix('/').unwrap_or(&req.uri.path),
                     file.name.as_str(),
                 );
                 let file_path = save_path.join(file.name);
 
+                if visibility.is_hidden(&file_path) {
+                    return Ok(Response::error(
+                        StatusCode::NOT_FOUND,
+                        Some(
+                            format!(
+                                "File <code>{}</code> does not exist",
+                                rel_path.to_str().unwrap()
+                            )
+                            .as_str(),
+                        ),
+                    ));
+                }
+
                 if !file_path.exists() {
                     fs::write(file_path, file.body)?;
                 } else {
                     return Ok(Response::error(
-                        400,
+                        StatusCode::BAD_REQUEST,
                         Some(
                             format!(
                                 "File <code>{}</code> already exists",
@@ -76,14 +95,10 @@ impl RequestHandler for Post {
                 file_paths.push(rel_path);
             }
         } else {
-            let req_path = req
-                .status_line
-                .uri
-                .strip_prefix("/")
-                .unwrap_or(req.status_line.uri);
+            let req_path = req.uri.path.strip_prefix('/').unwrap_or(&req.uri.path);
 
             let (filename, rel_path) = if is_filepath {
-                fs::create_dir_all(&save_path.parent().unwrap_or(&save_path))?;
+                fs::create_dir_all(save_path.parent().unwrap_or(&save_path))?;
 
                 let filename = save_path.file_name().unwrap().to_string_lossy().to_string();
 
@@ -95,14 +110,14 @@ impl RequestHandler for Post {
                     || {
                         let filename = random_string(6);
                         let extension =
-                            get_mime_extensions_str(req.headers.get("Content-Type").unwrap_or(&""))
+                            get_mime_extensions_str(req.headers.get("Content-Type").unwrap_or(""))
                                 .unwrap_or(&["txt"])[0];
                         format!("{}.{}", filename, extension)
                     },
-                    |&name| sanitise(name),
+                    sanitise,
                 );
 
-                let path = get_path(req_path, &filename.as_str());
+                let path = get_path(req_path, filename.as_str());
 
                 (filename, path)
             };
@@ -113,6 +128,19 @@ impl RequestHandler for Post {
                 save_path
             };
 
+            if visibility.is_hidden(&file_path) {
+                return Ok(Response::error(
+                    StatusCode::NOT_FOUND,
+                    Some(
+                        format!(
+                            "File <code>{}</code> does not exist",
+                            rel_path.to_str().unwrap()
+                        )
+                        .as_str(),
+                    ),
+                ));
+            }
+
             fs::write(file_path, req.body.unwrap())?;
             file_paths.push(rel_path);
         }
@@ -124,17 +152,37 @@ impl RequestHandler for Post {
             .map(|name| format!("http://{}/{}", host, name.to_str().unwrap()))
             .collect();
 
-        Ok(Response::ok(
-            201,
-            req.status_line.uri.parse()?,
-            (links.join("\n") + "\n").as_bytes().to_vec(),
-        ))
+        Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .content_type("text/plain")
+            .body((links.join("\n") + "\n").into_bytes())
+            .build())
+    }
+
+    fn check_headers<'a>(req: &'a Request<'a>, opts: Arc<Opts>) -> Option<Response<'a>> {
+        let save_path = get_path(opts.directory.as_str(), req.uri.path.as_str());
+
+        let content_type =
+            Request::parse_complex_header(req.headers.get("Content-Type").unwrap_or(""));
+
+        // a directory whose name looks like a file is not an existing file
+        if content_type.value != "multipart/form-data"
+            && is_filepath(&save_path)
+            && save_path.is_file()
+        {
+            return Some(Response::error(
+                StatusCode::BAD_REQUEST,
+                Some(format!("File <code>{}</code> already exists", req.uri.path).as_str()),
+            ));
+        }
+
+        None
     }
 }
 
 fn get_host(req: &Request, opts: Arc<Opts>) -> String {
     req.headers.get("Host").map_or_else(
         || format!("{}:{}", opts.host, opts.port),
-        |&host| host.to_string(),
+        |host| host.to_string(),
     )
 }
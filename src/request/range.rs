@@ -0,0 +1,204 @@
+/// Most ranges accepted in a single request.
+///
+/// Requests for more are served in full rather than split into
+/// a large number of tiny parts.
+const MAX_RANGES: usize = 64;
+
+/// An inclusive range of bytes within a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Gets the number of bytes covered by the range
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Formats the value of a `Content-Range` header for the range
+    pub fn content_range(&self, length: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, length)
+    }
+}
+
+/// The result of evaluating a `Range` header against a resource
+#[derive(Debug, PartialEq, Eq)]
+pub enum Ranges {
+    /// At least one range overlaps the resource.
+    /// Ranges which do not are dropped,
+    /// and the rest are sorted with any overlapping ones merged.
+    Satisfiable(Vec<ByteRange>),
+    /// None of the ranges overlap the resource
+    Unsatisfiable,
+}
+
+impl Ranges {
+    /// Parses a `Range` header as described in RFC 9110 section 14.2,
+    /// resolving each range against a resource of `length` bytes.
+    ///
+    /// Returns `None` if the header should be ignored and the whole resource sent,
+    /// which is the case for units other than `bytes` and for malformed headers.
+    pub fn parse(header: &str, length: u64) -> Option<Ranges> {
+        let (unit, specs) = header.split_once('=')?;
+
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+
+        let specs = specs
+            .split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .collect::<Vec<_>>();
+
+        if specs.is_empty() || specs.len() > MAX_RANGES {
+            return None;
+        }
+
+        let mut ranges = Vec::new();
+        for spec in specs {
+            if let Some(range) = parse_spec(spec, length)? {
+                ranges.push(range);
+            }
+        }
+
+        if ranges.is_empty() {
+            Some(Ranges::Unsatisfiable)
+        } else {
+            Some(Ranges::Satisfiable(coalesce(ranges)))
+        }
+    }
+}
+
+/// Sorts ranges and merges any which overlap or are adjacent,
+/// so that no byte is sent more than once.
+fn coalesce(mut ranges: Vec<ByteRange>) -> Vec<ByteRange> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+/// Parses a single range from a `Range` header.
+///
+/// Returns `None` if the range is malformed,
+/// or `Some(None)` if it is valid but lies outside the resource.
+fn parse_spec(spec: &str, length: u64) -> Option<Option<ByteRange>> {
+    let (first, last) = spec.split_once('-')?;
+    let (first, last) = (first.trim(), last.trim());
+
+    // suffix ranges ask for the final N bytes
+    if first.is_empty() {
+        let suffix = parse_number(last)?;
+
+        if suffix == 0 || length == 0 {
+            return Some(None);
+        }
+
+        return Some(Some(ByteRange {
+            start: length.saturating_sub(suffix),
+            end: length - 1,
+        }));
+    }
+
+    let start = parse_number(first)?;
+
+    let end = if last.is_empty() {
+        None
+    } else {
+        Some(parse_number(last)?)
+    };
+
+    if end.is_some_and(|end| end < start) {
+        return None;
+    }
+
+    if start >= length {
+        return Some(None);
+    }
+
+    Some(Some(ByteRange {
+        start,
+        end: end.map_or(length - 1, |end| end.min(length - 1)),
+    }))
+}
+
+/// Parses a run of ASCII digits, which is all a range position may contain
+fn parse_number(string: &str) -> Option<u64> {
+    if string.is_empty() || !string.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    string.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn satisfiable(header: &str, length: u64) -> Vec<(u64, u64)> {
+        match Ranges::parse(header, length) {
+            Some(Ranges::Satisfiable(ranges)) => ranges
+                .iter()
+                .map(|range| (range.start, range.end))
+                .collect(),
+            other => panic!("expected satisfiable ranges, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_closed_and_open_ended_ranges() {
+        assert_eq!(satisfiable("bytes=0-9", 100), vec![(0, 9)]);
+        assert_eq!(satisfiable("bytes=90-", 100), vec![(90, 99)]);
+        assert_eq!(satisfiable("bytes=90-200", 100), vec![(90, 99)]);
+    }
+
+    #[test]
+    fn parses_suffix_ranges() {
+        assert_eq!(satisfiable("bytes=-10", 100), vec![(90, 99)]);
+        assert_eq!(satisfiable("bytes=-200", 100), vec![(0, 99)]);
+    }
+
+    #[test]
+    fn detects_unsatisfiable_ranges() {
+        assert_eq!(
+            Ranges::parse("bytes=100-", 100),
+            Some(Ranges::Unsatisfiable)
+        );
+        assert_eq!(Ranges::parse("bytes=-0", 100), Some(Ranges::Unsatisfiable));
+        assert_eq!(Ranges::parse("bytes=0-", 0), Some(Ranges::Unsatisfiable));
+        assert_eq!(satisfiable("bytes=200-300, 0-0", 100), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn ignores_malformed_headers() {
+        assert_eq!(Ranges::parse("items=0-9", 100), None);
+        assert_eq!(Ranges::parse("bytes=9-0", 100), None);
+        assert_eq!(Ranges::parse("bytes=+1-2", 100), None);
+        assert_eq!(Ranges::parse("bytes=", 100), None);
+    }
+
+    #[test]
+    fn coalesces_overlapping_and_adjacent_ranges() {
+        let header = format!("bytes={}", vec!["0-"; MAX_RANGES].join(","));
+        assert_eq!(satisfiable(&header, 100), vec![(0, 99)]);
+
+        assert_eq!(
+            satisfiable("bytes=50-59,0-9,10-19", 100),
+            vec![(0, 19), (50, 59)]
+        );
+        assert_eq!(satisfiable("bytes=0-50,-60", 100), vec![(0, 99)]);
+        assert_eq!(satisfiable("bytes=0-9,20-29", 100), vec![(0, 9), (20, 29)]);
+    }
+}
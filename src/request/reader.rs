@@ -0,0 +1,462 @@
+use std::io;
+use std::io::prelude::*;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use crate::buffer_utils::find_substring;
+use crate::request::{Limits, ParseError, Request};
+
+/// Size of each individual read from the socket
+const READ_SIZE: usize = 8192;
+
+/// Reads complete HTTP requests from a stream.
+///
+/// Any bytes read past the end of a request are kept
+/// and used as the start of the next one.
+pub struct RequestReader {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    limits: Limits,
+    phase: Phase,
+}
+
+/// The part of a request the reader is waiting for,
+/// which decides how long it is prepared to wait.
+enum Phase {
+    /// Waiting for the first byte of the next request
+    Idle,
+    /// Receiving the status line and headers
+    Head { started: Instant },
+    /// Receiving the body
+    Body { started: Instant, received: usize },
+}
+
+impl RequestReader {
+    pub fn new(stream: TcpStream, limits: Limits) -> RequestReader {
+        RequestReader {
+            stream,
+            buffer: Vec::new(),
+            limits,
+            phase: Phase::Idle,
+        }
+    }
+
+    /// Reads the status line and headers of the next request,
+    /// up to and including the blank line which terminates them.
+    ///
+    /// Returns `None` if the connection was closed, or sat idle for too long,
+    /// before any part of a request was received.
+    pub fn read_head(&mut self) -> Result<Option<Vec<u8>>, ParseError> {
+        const PREDICATE: &[u8] = b"\r\n\r\n";
+
+        let mut searched: usize = 0;
+
+        self.phase = if self.buffer.is_empty() {
+            Phase::Idle
+        } else {
+            Phase::Head {
+                started: Instant::now(),
+            }
+        };
+
+        loop {
+            check_uri_length(&self.buffer, self.limits.max_uri_length)?;
+
+            let start = searched.saturating_sub(PREDICATE.len() - 1);
+            if let Some(pos) = find_substring(&self.buffer[start..], PREDICATE) {
+                let end = start + pos + PREDICATE.len();
+
+                if end > self.limits.max_header_bytes {
+                    return Err(ParseError::HeadersTooLarge);
+                }
+
+                return Ok(Some(self.buffer.drain(..end).collect()));
+            }
+
+            if self.buffer.len() > self.limits.max_header_bytes {
+                return Err(ParseError::HeadersTooLarge);
+            }
+
+            searched = self.buffer.len();
+
+            let read = match self.fill() {
+                Err(ParseError::Timeout) if self.buffer.is_empty() => return Ok(None),
+                read => read?,
+            };
+
+            if let Phase::Idle = self.phase {
+                self.phase = Phase::Head {
+                    started: Instant::now(),
+                };
+            }
+
+            if read == 0 {
+                return if self.buffer.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(ParseError::Io(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Connection closed before end of headers",
+                    )))
+                };
+            }
+        }
+    }
+
+    /// Reads the body of a request whose head has already been read.
+    ///
+    /// Bodies sent with `Transfer-Encoding: chunked` are decoded,
+    /// otherwise exactly `Content-Length` bytes are read,
+    /// however many reads from the socket that takes.
+    pub fn read_body(&mut self, req: &Request) -> Result<Option<Vec<u8>>, ParseError> {
+        self.phase = Phase::Body {
+            started: Instant::now(),
+            received: 0,
+        };
+
+        let body = if req.headers.contains_key("Transfer-Encoding") {
+            check_transfer_encoding(req)?;
+            self.read_chunked_body()?
+        } else {
+            match self.content_length(req)? {
+                Some(length) => self.read_exact(length)?,
+                None => return Ok(None),
+            }
+        };
+
+        Ok(if body.is_empty() { None } else { Some(body) })
+    }
+
+    /// Checks the framing headers of a request before its body is read,
+    /// so that bodies which cannot be accepted are rejected without being transferred.
+    pub fn check_body(&self, req: &Request) -> Result<(), ParseError> {
+        if req.headers.contains_key("Transfer-Encoding") {
+            check_transfer_encoding(req)
+        } else {
+            self.content_length(req).map(|_| ())
+        }
+    }
+
+    /// Gets the declared `Content-Length` of a request,
+    /// checking it against the maximum body size.
+    fn content_length(&self, req: &Request) -> Result<Option<usize>, ParseError> {
+        // conflicting lengths make it impossible to tell where the body ends
+        let mut lengths = req.headers.get_all("Content-Length");
+        if let Some(length) = lengths.next() {
+            if lengths.any(|other| other != length) {
+                return Err(ParseError::BadHeader);
+            }
+        }
+
+        let content_length = match req.headers.get("Content-Length") {
+            Some(length) => length
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| ParseError::BadHeader)?,
+            None => return Ok(None),
+        };
+
+        if self
+            .limits
+            .max_body_size
+            .is_some_and(|max_size| content_length > max_size)
+        {
+            return Err(ParseError::BodyTooLarge);
+        }
+
+        Ok(Some(content_length))
+    }
+
+    /// Decodes a body sent using the chunked transfer coding
+    /// as described in RFC 9112 section 7.1.
+    ///
+    /// Chunk extensions are ignored.
+    /// Trailer fields are read to find the end of the message,
+    /// but are discarded rather than merged into the headers.
+    fn read_chunked_body(&mut self) -> Result<Vec<u8>, ParseError> {
+        let mut body = Vec::new();
+
+        loop {
+            let line = self.read_line()?;
+            let size = parse_chunk_size(&line)?;
+
+            if size == 0 {
+                break;
+            }
+
+            if self.limits.max_body_size.is_some_and(|max_size| {
+                body.len()
+                    .checked_add(size)
+                    .is_none_or(|total| total > max_size)
+            }) {
+                return Err(ParseError::BodyTooLarge);
+            }
+
+            body.extend(self.read_exact(size)?);
+
+            // chunk data must be followed by CRLF
+            if !self.read_line()?.is_empty() {
+                return Err(ParseError::MalformedBody);
+            }
+        }
+
+        // trailer section ends with an empty line
+        loop {
+            let line = self.read_line()?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            if !line.contains(&b':') {
+                return Err(ParseError::MalformedBody);
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// Reads a single CRLF-terminated line, returning it without the line ending.
+    fn read_line(&mut self) -> Result<Vec<u8>, ParseError> {
+        const PREDICATE: &[u8] = b"\r\n";
+
+        let mut searched: usize = 0;
+
+        loop {
+            let start = searched.saturating_sub(PREDICATE.len() - 1);
+            if let Some(pos) = find_substring(&self.buffer[start..], PREDICATE) {
+                let end = start + pos;
+                let line = self.buffer.drain(..end).collect();
+                self.buffer.drain(..PREDICATE.len());
+                return Ok(line);
+            }
+
+            if self.buffer.len() > self.limits.max_header_bytes {
+                return Err(ParseError::MalformedBody);
+            }
+
+            searched = self.buffer.len();
+            self.fill_or_eof()?;
+        }
+    }
+
+    /// Reads exactly `length` bytes from the stream.
+    fn read_exact(&mut self, length: usize) -> Result<Vec<u8>, ParseError> {
+        while self.buffer.len() < length {
+            self.fill_or_eof()?;
+        }
+
+        Ok(self.buffer.drain(..length).collect())
+    }
+
+    /// Performs a single read from the stream,
+    /// treating a closed connection as an error.
+    fn fill_or_eof(&mut self) -> Result<(), ParseError> {
+        if self.fill()? == 0 {
+            Err(ParseError::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Connection closed before end of request",
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Performs a single read from the stream into the buffer,
+    /// returning the number of bytes read.
+    ///
+    /// The read gives up with `ParseError::Timeout`
+    /// if it cannot complete within the time allowed for the current phase.
+    fn fill(&mut self) -> Result<usize, ParseError> {
+        let timeout = self.read_timeout().ok_or(ParseError::Timeout)?;
+        self.stream.set_read_timeout(Some(timeout))?;
+
+        let mut chunk = [0; READ_SIZE];
+        let read = match self.stream.read(&mut chunk) {
+            Ok(read) => read,
+            Err(err) if is_timeout(&err) => return Err(ParseError::Timeout),
+            Err(err) => return Err(err.into()),
+        };
+
+        self.buffer.extend_from_slice(&chunk[..read]);
+
+        if let Phase::Body { received, .. } = &mut self.phase {
+            *received += read;
+        }
+
+        Ok(read)
+    }
+
+    /// Gets how long the next read may wait,
+    /// or `None` if the time allowed has already run out.
+    ///
+    /// Headers must arrive in full within the header timeout.
+    /// Each read of the body may wait up to the body timeout,
+    /// and if a minimum rate is set the body as a whole must keep up with it,
+    /// with the body timeout as a grace period.
+    fn read_timeout(&self) -> Option<Duration> {
+        let limits = &self.limits;
+
+        let timeout = match self.phase {
+            Phase::Idle => limits.keep_alive_timeout,
+            Phase::Head { started } => {
+                (started + limits.header_timeout).checked_duration_since(Instant::now())?
+            }
+            Phase::Body { started, received } => match limits.min_body_rate {
+                Some(rate) => {
+                    let allowed = Duration::from_secs_f64(received as f64 / rate as f64);
+                    let deadline = started + limits.body_timeout + allowed;
+
+                    deadline
+                        .checked_duration_since(Instant::now())?
+                        .min(limits.body_timeout)
+                }
+                None => limits.body_timeout,
+            },
+        };
+
+        // a zero timeout would block forever
+        if timeout.is_zero() {
+            None
+        } else {
+            Some(timeout)
+        }
+    }
+}
+
+/// Parses the hexadecimal size from a chunk header line,
+/// discarding any chunk extensions.
+fn parse_chunk_size(line: &[u8]) -> Result<usize, ParseError> {
+    let line = std::str::from_utf8(line).map_err(|_| ParseError::MalformedBody)?;
+
+    let size = line.split(';').next().unwrap_or("").trim();
+
+    // from_str_radix would also accept a sign
+    if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(ParseError::MalformedBody);
+    }
+
+    usize::from_str_radix(size, 16).map_err(|_| ParseError::MalformedBody)
+}
+
+/// Checks the length of the URI in a partly received request line,
+/// so that a long URI is reported as such
+/// rather than as headers which are too large.
+fn check_uri_length(buffer: &[u8], max_uri_length: usize) -> Result<(), ParseError> {
+    let line_end = find_substring(buffer, b"\r\n").unwrap_or(buffer.len());
+    let line = &buffer[..line_end];
+
+    let uri = match line.iter().position(|&byte| byte == b' ') {
+        Some(method_end) => &line[method_end + 1..],
+        None => return Ok(()),
+    };
+    let uri_length = uri
+        .iter()
+        .position(|&byte| byte == b' ')
+        .unwrap_or(uri.len());
+
+    if uri_length > max_uri_length {
+        Err(ParseError::UriTooLong)
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that chunked is the final transfer coding applied,
+/// as it is the only one supported.
+///
+/// Requests which also give a `Content-Length` are rejected,
+/// as a proxy in front of the server could disagree about where the body ends,
+/// which is how requests are smuggled past it.
+fn check_transfer_encoding(req: &Request) -> Result<(), ParseError> {
+    if req.headers.contains_key("Content-Length") {
+        return Err(ParseError::BadHeader);
+    }
+
+    let is_chunked = req
+        .headers
+        .get_all("Transfer-Encoding")
+        .flat_map(|value| value.split(','))
+        .last()
+        .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"));
+
+    if is_chunked {
+        Ok(())
+    } else {
+        Err(ParseError::UnsupportedTransferEncoding)
+    }
+}
+
+/// Checks whether an IO error was caused by a socket timeout
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(head: &[u8]) -> Request<'_> {
+        Request::from_bytes(head, Limits::default()).unwrap()
+    }
+
+    #[test]
+    fn checks_uri_length_before_the_line_ends() {
+        let line = format!("GET /{} HTTP/1.1\r\n", "a".repeat(20));
+        assert!(check_uri_length(line.as_bytes(), 21).is_ok());
+        assert!(matches!(
+            check_uri_length(line.as_bytes(), 20),
+            Err(ParseError::UriTooLong)
+        ));
+
+        let partial = format!("GET /{}", "a".repeat(20));
+        assert!(matches!(
+            check_uri_length(partial.as_bytes(), 20),
+            Err(ParseError::UriTooLong)
+        ));
+        assert!(check_uri_length(b"GET", 0).is_ok());
+        assert!(check_uri_length(b"", 0).is_ok());
+    }
+
+    #[test]
+    fn accepts_chunked_bodies() {
+        let req = request(b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n");
+        assert!(check_transfer_encoding(&req).is_ok());
+    }
+
+    #[test]
+    fn rejects_unsupported_transfer_codings() {
+        let req = request(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n");
+        assert!(matches!(
+            check_transfer_encoding(&req),
+            Err(ParseError::UnsupportedTransferEncoding)
+        ));
+    }
+
+    #[test]
+    fn rejects_chunked_bodies_with_a_content_length() {
+        let req =
+            request(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 5\r\n\r\n");
+        let err = check_transfer_encoding(&req).unwrap_err();
+        assert_eq!(err.status_code(), crate::StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn parses_chunk_sizes() {
+        assert_eq!(parse_chunk_size(b"1a").unwrap(), 0x1a);
+        assert_eq!(parse_chunk_size(b"FF;name=value").unwrap(), 0xff);
+        assert_eq!(parse_chunk_size(b"0").unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_chunk_sizes() {
+        assert!(parse_chunk_size(b"+1a").is_err());
+        assert!(parse_chunk_size(b"-1").is_err());
+        assert!(parse_chunk_size(b"").is_err());
+        assert!(parse_chunk_size(b"0x10").is_err());
+        assert!(parse_chunk_size(b"10000000000000000").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs::Metadata;
+use std::io;
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, Utc};
+
+use crate::request::Request;
+
+/// Format of an HTTP date, as described in RFC 9110 section 5.6.7
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Validators which identify a version of a file,
+/// letting clients check whether their copy is still current.
+pub struct Validators {
+    /// Strong entity tag, including its quotes
+    pub etag: String,
+    /// Last modification time of the file
+    pub last_modified: DateTime<Utc>,
+}
+
+impl Validators {
+    /// Gets the validators for a file from its metadata.
+    ///
+    /// The entity tag is built from the modification time and size,
+    /// so it changes whenever the file is written to.
+    pub fn from_metadata(metadata: &Metadata) -> io::Result<Validators> {
+        let modified = metadata.modified()?;
+        // files dated before the epoch all share the same tag
+        let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        Ok(Validators {
+            etag: format!(
+                "\"{:x}-{:x}-{:x}\"",
+                since_epoch.as_secs(),
+                since_epoch.subsec_nanos(),
+                metadata.len()
+            ),
+            last_modified: DateTime::from(modified),
+        })
+    }
+
+    /// Formats the value of the `Last-Modified` header
+    pub fn last_modified_header(&self) -> String {
+        self.last_modified.format(HTTP_DATE_FORMAT).to_string()
+    }
+
+    /// Checks whether the client's cached copy is current,
+    /// meaning a `304 Not Modified` response can be sent instead of the file.
+    ///
+    /// `If-None-Match` takes precedence over `If-Modified-Since`,
+    /// following RFC 9110 section 13.2.2.
+    pub fn is_not_modified(&self, req: &Request) -> bool {
+        if !matches!(req.status_line.method, "GET" | "HEAD") {
+            return false;
+        }
+
+        if req.headers.contains_key("If-None-Match") {
+            return req
+                .headers
+                .get_all("If-None-Match")
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .any(|tag| tag == "*" || weak_eq(tag, &self.etag));
+        }
+
+        match req
+            .headers
+            .get("If-Modified-Since")
+            .and_then(parse_http_date)
+        {
+            Some(date) => self.last_modified.timestamp() <= date.timestamp(),
+            None => false,
+        }
+    }
+
+    /// Checks whether the value of an `If-Range` header
+    /// still describes the current version of the file.
+    ///
+    /// Entity tags must match exactly and cannot be weak,
+    /// and dates must be the exact modification time.
+    pub fn if_range_matches(&self, if_range: &str) -> bool {
+        let if_range = if_range.trim();
+
+        if if_range.starts_with('"') {
+            return if_range == self.etag;
+        }
+
+        match parse_http_date(if_range) {
+            Some(date) => self.last_modified.timestamp() == date.timestamp(),
+            None => false,
+        }
+    }
+}
+
+/// Compares two entity tags, ignoring whether either is weak
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// Parses an HTTP date.
+///
+/// Only the preferred IMF-fixdate format is understood,
+/// which clients send back exactly as the server formatted it.
+fn parse_http_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(date.trim())
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
@@ -0,0 +1,120 @@
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+
+use crate::request::ByteRange;
+
+/// Size of each chunk of a chunked body
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// The content of a response.
+///
+/// Small, generated bodies are held in memory,
+/// whereas files are streamed from their source
+/// so that memory use does not grow with the size of the file.
+/// Bodies whose length is not known until they have been generated
+/// are sent using the chunked transfer coding.
+pub enum Body {
+    Bytes(Vec<u8>),
+    Stream {
+        reader: Box<dyn Read + Send>,
+        length: u64,
+    },
+    Chunked(Box<dyn Read + Send>),
+}
+
+impl Body {
+    /// Creates a body which streams the contents of a file
+    pub fn from_file(file: File) -> io::Result<Body> {
+        let length = file.metadata()?.len();
+
+        Ok(Body::Stream {
+            reader: Box::new(file),
+            length,
+        })
+    }
+
+    /// Creates a body which streams a single range of a file
+    pub fn from_file_range(mut file: File, range: ByteRange) -> io::Result<Body> {
+        file.seek(SeekFrom::Start(range.start))?;
+
+        Ok(Body::Stream {
+            reader: Box::new(file),
+            length: range.length(),
+        })
+    }
+
+    /// Gets the length of the body in bytes,
+    /// or `None` if it is only known once the body has been sent
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::Stream { length, .. } => Some(*length),
+            Body::Chunked(_) => None,
+        }
+    }
+
+    /// Checks whether the body is known to have no content
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Checks whether the body is sent using the chunked transfer coding
+    pub fn is_chunked(&self) -> bool {
+        matches!(self, Body::Chunked(_))
+    }
+
+    /// Converts a chunked body into one which is sent as-is
+    /// and ends when the connection is closed,
+    /// for clients which do not understand chunked bodies.
+    pub fn unchunked(self) -> Body {
+        match self {
+            Body::Chunked(reader) => Body::Stream {
+                reader,
+                length: u64::MAX,
+            },
+            body => body,
+        }
+    }
+
+    /// Writes the body to the given stream.
+    ///
+    /// Streamed bodies are copied across in bounded chunks
+    /// rather than being read into memory in full.
+    pub fn write_to<W: Write>(&mut self, stream: &mut W) -> io::Result<()> {
+        match self {
+            Body::Bytes(bytes) => stream.write_all(bytes),
+            Body::Stream { reader, length } => {
+                io::copy(&mut reader.take(*length), stream)?;
+                Ok(())
+            }
+            Body::Chunked(reader) => {
+                let mut chunk = vec![0; CHUNK_SIZE];
+
+                loop {
+                    let read = match reader.read(&mut chunk) {
+                        Ok(read) => read,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(err) => return Err(err),
+                    };
+
+                    // a zero-sized chunk marks the end of the body
+                    write!(stream, "{:x}\r\n", read)?;
+                    stream.write_all(&chunk[..read])?;
+                    stream.write_all(b"\r\n")?;
+
+                    if read == 0 {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body::Bytes(bytes)
+    }
+}
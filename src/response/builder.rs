@@ -0,0 +1,93 @@
+use chrono::Local;
+
+use crate::response::{Body, Response};
+use crate::{HeaderMap, StatusCode, HTTP_VERSION};
+
+/// Builds up a `Response` one part at a time.
+///
+/// `Server`, `Date` and `Content-Length` or `Transfer-Encoding` headers
+/// are added automatically.
+pub struct ResponseBuilder {
+    status_code: StatusCode,
+    headers: HeaderMap,
+    body: Option<Body>,
+}
+
+impl ResponseBuilder {
+    pub fn new() -> ResponseBuilder {
+        ResponseBuilder {
+            status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    /// Sets the status code, which defaults to `200 OK`
+    pub fn status(mut self, status_code: StatusCode) -> ResponseBuilder {
+        self.status_code = status_code;
+        self
+    }
+
+    /// Adds a header, keeping any existing values with the same name
+    pub fn header<N: Into<String>, V: Into<String>>(
+        mut self,
+        name: N,
+        value: V,
+    ) -> ResponseBuilder {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Sets the `Content-Type` header
+    pub fn content_type<V: Into<String>>(mut self, content_type: V) -> ResponseBuilder {
+        self.headers.insert("Content-Type", content_type);
+        self
+    }
+
+    /// Sets the body
+    pub fn body<B: Into<Body>>(mut self, body: B) -> ResponseBuilder {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Redirects the client to another location
+    /// using the given `3xx` status code.
+    pub fn redirect<L: Into<String>>(
+        mut self,
+        status_code: StatusCode,
+        location: L,
+    ) -> ResponseBuilder {
+        self.status_code = status_code;
+        self.headers.insert("Location", location);
+        self
+    }
+
+    pub fn build<'a>(self) -> Response<'a> {
+        let mut headers = HeaderMap::new();
+
+        headers.insert("Server", "SimpleHTTP/0.1 Rust");
+        headers.insert("Date", Local::now().to_rfc2822());
+
+        for (name, value) in self.headers.iter() {
+            headers.append(name, value);
+        }
+
+        match self.body.as_ref().map_or(Some(0), Body::len) {
+            Some(content_length) => headers.insert("Content-Length", content_length.to_string()),
+            None => headers.insert("Transfer-Encoding", "chunked"),
+        }
+
+        Response {
+            http_version: HTTP_VERSION,
+            status_code: self.status_code,
+            headers,
+            body: self.body,
+        }
+    }
+}
+
+impl Default for ResponseBuilder {
+    fn default() -> Self {
+        ResponseBuilder::new()
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::{Cursor, SeekFrom};
+
+use crate::request::ByteRange;
+
+/// Reads several ranges of a file
+/// as the parts of a `multipart/byteranges` body,
+/// as described in RFC 9110 section 14.6.
+///
+/// Each range is read from the file only when it is reached,
+/// so memory use does not grow with the size of the ranges.
+pub struct ByteRanges {
+    file: File,
+    parts: VecDeque<Part>,
+}
+
+/// A piece of the multipart body
+enum Part {
+    /// A boundary delimiter and part headers
+    Bytes(Cursor<Vec<u8>>),
+    /// The part of the file still to be read for a range
+    Range { position: u64, remaining: u64 },
+}
+
+impl ByteRanges {
+    /// Creates a reader for the given ranges of a file,
+    /// returning it along with the total length of the body.
+    pub fn new(
+        file: File,
+        ranges: &[ByteRange],
+        length: u64,
+        content_type: &str,
+        boundary: &str,
+    ) -> (ByteRanges, u64) {
+        let mut parts = VecDeque::new();
+        let mut total = 0;
+
+        for (i, range) in ranges.iter().enumerate() {
+            // every part after the first is separated from the previous one's data
+            let delimiter = if i == 0 { "" } else { "\r\n" };
+
+            let head = format!(
+                "{}--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                delimiter,
+                boundary,
+                content_type,
+                range.content_range(length)
+            )
+            .into_bytes();
+
+            total += head.len() as u64 + range.length();
+
+            parts.push_back(Part::Bytes(Cursor::new(head)));
+            parts.push_back(Part::Range {
+                position: range.start,
+                remaining: range.length(),
+            });
+        }
+
+        let close = format!("\r\n--{}--\r\n", boundary).into_bytes();
+        total += close.len() as u64;
+        parts.push_back(Part::Bytes(Cursor::new(close)));
+
+        (ByteRanges { file, parts }, total)
+    }
+}
+
+impl Read for ByteRanges {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while let Some(part) = self.parts.front_mut() {
+            let read = match part {
+                Part::Bytes(bytes) => bytes.read(buf)?,
+                Part::Range {
+                    position,
+                    remaining,
+                } if *remaining > 0 => {
+                    let max = buf.len().min(*remaining as usize);
+
+                    self.file.seek(SeekFrom::Start(*position))?;
+                    let read = self.file.read(&mut buf[..max])?;
+
+                    // the file has shrunk since the response was started
+                    if read == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "File ended before the requested range",
+                        ));
+                    }
+
+                    *position += read as u64;
+                    *remaining -= read as u64;
+                    read
+                }
+                Part::Range { .. } => 0,
+            };
+
+            if read > 0 {
+                return Ok(read);
+            }
+
+            self.parts.pop_front();
+        }
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+
+pub use body::Body;
+pub use builder::ResponseBuilder;
+
+use crate::path_utils::random_string;
+use crate::request::ByteRange;
+use crate::{HeaderMap, StatusCode};
+use byteranges::ByteRanges;
+use std::path::PathBuf;
+
+mod body;
+mod builder;
+mod byteranges;
+
+pub struct Response<'a> {
+    pub http_version: &'a str,
+    pub status_code: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Option<Body>,
+}
+
+impl Response<'_> {
+    /// Writes the formatted HTTP response to the given stream
+    /// so that it can be sent back to the client.
+    pub fn write_to<W: Write>(&mut self, stream: &mut W) -> io::Result<()> {
+        let status_line = format!(
+            "{} {} {}",
+            self.http_version,
+            self.status_code,
+            self.status_code.reason_phrase()
+        );
+
+        // informational and 204 responses cannot have a body,
+        // and 304 responses describe a body without sending it
+        let code = self.status_code;
+        let has_body = !(code.is_informational()
+            || code == StatusCode::NO_CONTENT
+            || code == StatusCode::NOT_MODIFIED);
+
+        if !has_body {
+            self.headers.remove("Content-Length");
+        }
+
+        let headers = self
+            .headers
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect::<Vec<String>>()
+            .join("\r\n");
+
+        let head = format!("{}\r\n{}\r\n\r\n", status_line, headers);
+        stream.write_all(head.as_bytes())?;
+
+        if let Some(body) = self.body.as_mut().filter(|_| has_body) {
+            body.write_to(stream)?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts building a new response
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::new()
+    }
+
+    /// Guesses the MIME type of a file from its path,
+    /// falling back to HTML
+    pub fn guess_content_type(path: &str) -> String {
+        mime_guess::from_path(path)
+            .first_or("text/html".parse().unwrap())
+            .to_string()
+    }
+
+    /// Returns a successful response,
+    /// with the content type guessed from the given path
+    pub fn ok<'a>(status_code: StatusCode, path: PathBuf, content: Vec<u8>) -> Response<'a> {
+        Response::builder()
+            .status(status_code)
+            .content_type(Response::guess_content_type(path.to_str().unwrap()))
+            .body(content)
+            .build()
+    }
+
+    /// Returns an HTTP OK response which streams the contents of a file
+    pub fn file<'a>(path: PathBuf) -> io::Result<Response<'a>> {
+        let body = Body::from_file(File::open(&path)?)?;
+
+        Ok(Response::builder()
+            .content_type(Response::guess_content_type(path.to_str().unwrap()))
+            .header("Accept-Ranges", "bytes")
+            .body(body)
+            .build())
+    }
+
+    /// Returns an HTTP Partial Content response
+    /// which streams a single range of a file
+    pub fn file_range<'a>(path: PathBuf, range: ByteRange) -> io::Result<Response<'a>> {
+        let file = File::open(&path)?;
+        let length = file.metadata()?.len();
+
+        Ok(Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .content_type(Response::guess_content_type(path.to_str().unwrap()))
+            .header("Accept-Ranges", "bytes")
+            .header("Content-Range", range.content_range(length))
+            .body(Body::from_file_range(file, range)?)
+            .build())
+    }
+
+    /// Returns an HTTP Partial Content response
+    /// which streams several ranges of a file as a `multipart/byteranges` body
+    pub fn file_ranges<'a>(path: PathBuf, ranges: &[ByteRange]) -> io::Result<Response<'a>> {
+        let file = File::open(&path)?;
+        let length = file.metadata()?.len();
+
+        let content_type = Response::guess_content_type(path.to_str().unwrap());
+        let boundary = random_string(24);
+
+        let (reader, body_length) =
+            ByteRanges::new(file, ranges, length, content_type.as_str(), boundary.as_str());
+
+        Ok(Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .content_type(format!("multipart/byteranges; boundary={}", boundary))
+            .header("Accept-Ranges", "bytes")
+            .body(Body::Stream {
+                reader: Box::new(reader),
+                length: body_length,
+            })
+            .build())
+    }
+
+    /// Returns an HTTP Range Not Satisfiable response
+    /// for a resource of `length` bytes
+    pub fn range_not_satisfiable<'a>(length: u64) -> Response<'a> {
+        let mut response = Response::error(
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            Some("None of the requested ranges overlap the file"),
+        );
+        response
+            .headers
+            .insert("Content-Range", format!("bytes */{}", length));
+
+        response
+    }
+
+    /// Returns an error response for the given error code.
+    pub fn error<'a>(status_code: StatusCode, details: Option<&str>) -> Response<'a> {
+        let content = Response::get_error_html(status_code, details);
+
+        Response::builder()
+            .status(status_code)
+            .content_type("text/html")
+            .body(content)
+            .build()
+    }
+
+    /// Loads the HTML page for the given error code
+    fn get_error_html(code: StatusCode, details: Option<&str>) -> Vec<u8> {
+        format!(
+            "<!DOCTYPE html> \
+            <html lang=\"en\"> \
+            <head> \
+                <meta charset=\"UTF-8\"> \
+                <title>{code} | {reason_phrase}</title> \
+            </head> \
+            <body> \
+                <h1>{reason_phrase}</h1> \
+                <p>{details}</p> \
+            </body> \
+            </html>",
+            code = code,
+            reason_phrase = code.reason_phrase(),
+            details = details.unwrap_or("")
+        )
+        .as_bytes()
+        .to_vec()
+    }
+}
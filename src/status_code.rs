@@ -0,0 +1,174 @@
+use std::fmt;
+
+/// An HTTP response status code.
+///
+/// Constants are provided for every code in the RFC 9110 registry,
+/// along with the additional codes from RFC 6585.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatusCode(u16);
+
+impl StatusCode {
+    pub const CONTINUE: StatusCode = StatusCode(100);
+    pub const SWITCHING_PROTOCOLS: StatusCode = StatusCode(101);
+    pub const OK: StatusCode = StatusCode(200);
+    pub const CREATED: StatusCode = StatusCode(201);
+    pub const ACCEPTED: StatusCode = StatusCode(202);
+    pub const NON_AUTHORITATIVE_INFORMATION: StatusCode = StatusCode(203);
+    pub const NO_CONTENT: StatusCode = StatusCode(204);
+    pub const RESET_CONTENT: StatusCode = StatusCode(205);
+    pub const PARTIAL_CONTENT: StatusCode = StatusCode(206);
+    pub const MULTIPLE_CHOICES: StatusCode = StatusCode(300);
+    pub const MOVED_PERMANENTLY: StatusCode = StatusCode(301);
+    pub const FOUND: StatusCode = StatusCode(302);
+    pub const SEE_OTHER: StatusCode = StatusCode(303);
+    pub const NOT_MODIFIED: StatusCode = StatusCode(304);
+    pub const USE_PROXY: StatusCode = StatusCode(305);
+    pub const TEMPORARY_REDIRECT: StatusCode = StatusCode(307);
+    pub const PERMANENT_REDIRECT: StatusCode = StatusCode(308);
+    pub const BAD_REQUEST: StatusCode = StatusCode(400);
+    pub const UNAUTHORIZED: StatusCode = StatusCode(401);
+    pub const PAYMENT_REQUIRED: StatusCode = StatusCode(402);
+    pub const FORBIDDEN: StatusCode = StatusCode(403);
+    pub const NOT_FOUND: StatusCode = StatusCode(404);
+    pub const METHOD_NOT_ALLOWED: StatusCode = StatusCode(405);
+    pub const NOT_ACCEPTABLE: StatusCode = StatusCode(406);
+    pub const PROXY_AUTHENTICATION_REQUIRED: StatusCode = StatusCode(407);
+    pub const REQUEST_TIMEOUT: StatusCode = StatusCode(408);
+    pub const CONFLICT: StatusCode = StatusCode(409);
+    pub const GONE: StatusCode = StatusCode(410);
+    pub const LENGTH_REQUIRED: StatusCode = StatusCode(411);
+    pub const PRECONDITION_FAILED: StatusCode = StatusCode(412);
+    pub const CONTENT_TOO_LARGE: StatusCode = StatusCode(413);
+    pub const URI_TOO_LONG: StatusCode = StatusCode(414);
+    pub const UNSUPPORTED_MEDIA_TYPE: StatusCode = StatusCode(415);
+    pub const RANGE_NOT_SATISFIABLE: StatusCode = StatusCode(416);
+    pub const EXPECTATION_FAILED: StatusCode = StatusCode(417);
+    pub const MISDIRECTED_REQUEST: StatusCode = StatusCode(421);
+    pub const UNPROCESSABLE_CONTENT: StatusCode = StatusCode(422);
+    pub const UPGRADE_REQUIRED: StatusCode = StatusCode(426);
+    pub const PRECONDITION_REQUIRED: StatusCode = StatusCode(428);
+    pub const TOO_MANY_REQUESTS: StatusCode = StatusCode(429);
+    pub const REQUEST_HEADER_FIELDS_TOO_LARGE: StatusCode = StatusCode(431);
+    pub const INTERNAL_SERVER_ERROR: StatusCode = StatusCode(500);
+    pub const NOT_IMPLEMENTED: StatusCode = StatusCode(501);
+    pub const BAD_GATEWAY: StatusCode = StatusCode(502);
+    pub const SERVICE_UNAVAILABLE: StatusCode = StatusCode(503);
+    pub const GATEWAY_TIMEOUT: StatusCode = StatusCode(504);
+    pub const HTTP_VERSION_NOT_SUPPORTED: StatusCode = StatusCode(505);
+    pub const NETWORK_AUTHENTICATION_REQUIRED: StatusCode = StatusCode(511);
+
+    /// Creates a status code from its numeric value.
+    ///
+    /// Returns `None` if the value is outside of the valid `100..=599` range.
+    pub fn from_u16(code: u16) -> Option<StatusCode> {
+        if (100..=599).contains(&code) {
+            Some(StatusCode(code))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+
+    /// Gets the reason phrase for the status code.
+    ///
+    /// Unregistered codes fall back to a phrase describing their class.
+    pub fn reason_phrase(&self) -> &'static str {
+        match self.0 {
+            100 => "Continue",
+            101 => "Switching Protocols",
+            200 => "OK",
+            201 => "Created",
+            202 => "Accepted",
+            203 => "Non-Authoritative Information",
+            204 => "No Content",
+            205 => "Reset Content",
+            206 => "Partial Content",
+            300 => "Multiple Choices",
+            301 => "Moved Permanently",
+            302 => "Found",
+            303 => "See Other",
+            304 => "Not Modified",
+            305 => "Use Proxy",
+            307 => "Temporary Redirect",
+            308 => "Permanent Redirect",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            402 => "Payment Required",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            406 => "Not Acceptable",
+            407 => "Proxy Authentication Required",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            410 => "Gone",
+            411 => "Length Required",
+            412 => "Precondition Failed",
+            413 => "Content Too Large",
+            414 => "URI Too Long",
+            415 => "Unsupported Media Type",
+            416 => "Range Not Satisfiable",
+            417 => "Expectation Failed",
+            421 => "Misdirected Request",
+            422 => "Unprocessable Content",
+            426 => "Upgrade Required",
+            428 => "Precondition Required",
+            429 => "Too Many Requests",
+            431 => "Request Header Fields Too Large",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            502 => "Bad Gateway",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            505 => "HTTP Version Not Supported",
+            511 => "Network Authentication Required",
+            _ => match self.0 / 100 {
+                1 => "Informational",
+                2 => "Success",
+                3 => "Redirection",
+                4 => "Client Error",
+                _ => "Server Error",
+            },
+        }
+    }
+
+    /// Checks whether the status code is `1xx`
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.0)
+    }
+
+    /// Checks whether the status code is `2xx`
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.0)
+    }
+
+    /// Checks whether the status code is `3xx`
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.0)
+    }
+
+    /// Checks whether the status code is `4xx`
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.0)
+    }
+
+    /// Checks whether the status code is `5xx`
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.0)
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<StatusCode> for u16 {
+    fn from(code: StatusCode) -> Self {
+        code.0
+    }
+}
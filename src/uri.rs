@@ -0,0 +1,123 @@
+use urlencoding::decode;
+
+/// A parsed request target.
+///
+/// The path is percent-decoded and has its dot-segments resolved,
+/// the query string is split into its parameters,
+/// and any fragment is dropped.
+#[derive(Debug, Clone)]
+pub struct Uri {
+    pub path: String,
+    pub query: Vec<(String, String)>,
+}
+
+impl Uri {
+    /// Parses a raw request target such as `/files/a%20b.txt?v=2#top`.
+    ///
+    /// Returns `None` if the target contains invalid percent-encoding,
+    /// or is neither `*` nor a path starting with `/`.
+    pub fn parse(raw: &str) -> Option<Uri> {
+        let raw = raw.split('#').next().unwrap_or("");
+
+        // absolute-form targets include the scheme and authority
+        let raw = match raw.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+            None => raw,
+        };
+
+        let (path, query) = raw.split_once('?').unwrap_or((raw, ""));
+
+        let path = decode(path).ok()?;
+        // anything else could reach outside the served directory
+        let path = if path == "*" {
+            path
+        } else if path.starts_with('/') {
+            remove_dot_segments(&path)
+        } else {
+            return None;
+        };
+
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                Some((decode_query(key)?, decode_query(value)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Uri { path, query })
+    }
+
+    /// Gets the first value of a query parameter
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Gets every value of a query parameter, in the order they appear
+    pub fn query_params<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.query
+            .iter()
+            .filter(move |(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Decodes a query string component, where `+` stands for a space
+fn decode_query(component: &str) -> Option<String> {
+    decode(&component.replace('+', " ")).ok()
+}
+
+/// Resolves `.` and `..` segments and collapses repeated slashes
+/// so that the path can never point above the root.
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    // keep the trailing slash for directory paths such as `/dir/` or `/dir/..`
+    let trailing_slash = path.ends_with('/') || path.ends_with("/.") || path.ends_with("/..");
+
+    if trailing_slash && !segments.is_empty() {
+        format!("/{}/", segments.join("/"))
+    } else {
+        format!("/{}", segments.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_relative_targets() {
+        assert!(Uri::parse("../x").is_none());
+        assert!(Uri::parse("x").is_none());
+        assert!(Uri::parse("%2E%2E/x").is_none());
+    }
+
+    #[test]
+    fn resolves_dot_segments_within_the_root() {
+        assert_eq!(Uri::parse("/a/../../x").unwrap().path, "/x");
+        assert_eq!(Uri::parse("/a/%2E%2E/%2E%2E/x").unwrap().path, "/x");
+        assert_eq!(Uri::parse("/a/./b/..").unwrap().path, "/a/");
+        assert_eq!(Uri::parse("http://host/../x").unwrap().path, "/x");
+        assert_eq!(Uri::parse("http://host").unwrap().path, "/");
+    }
+
+    #[test]
+    fn keeps_asterisk_form() {
+        assert_eq!(Uri::parse("*").unwrap().path, "*");
+    }
+}
@@ -0,0 +1,314 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use crate::request::Request;
+use crate::{glob, Opts, Response, StatusCode};
+
+/// Name of the file, in the root of the served directory,
+/// which lists paths to hide using gitignore syntax
+pub const IGNORE_FILE: &str = ".httpignore";
+
+/// A single pattern from an ignore file
+#[derive(Debug, Clone)]
+pub struct IgnoreRule {
+    pattern: String,
+    /// Whether the pattern starts with `!`, un-hiding paths hidden by earlier rules
+    negated: bool,
+    /// Whether the pattern ends with `/`, so only matches directories
+    dir_only: bool,
+    /// Whether the pattern contains a `/`,
+    /// so is matched against the whole path rather than the name
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    /// Parses a line of an ignore file,
+    /// returning `None` for blank lines and comments.
+    pub fn parse(line: &str) -> Option<IgnoreRule> {
+        // trailing spaces are ignored unless escaped
+        let mut line = line.trim_end_matches(&['\r', '\n'][..]);
+        while line.ends_with(' ') && !line.ends_with("\\ ") {
+            line = &line[..line.len() - 1];
+        }
+
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        // escaped leading characters are taken literally
+        let line = if line.starts_with("\\!") || line.starts_with("\\#") {
+            &line[1..]
+        } else {
+            line
+        };
+
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(IgnoreRule {
+            anchored: line.contains('/'),
+            pattern: line.trim_start_matches('/').to_string(),
+            negated,
+            dir_only,
+        })
+    }
+
+    /// Checks whether the rule matches a `/`-separated path relative to the root
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        if self.anchored {
+            glob::matches(&self.pattern, path)
+        } else {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            glob::matches(&self.pattern, name)
+        }
+    }
+}
+
+/// Which symlinks under the served directory may be followed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow every symlink, wherever it points
+    Follow,
+    /// Only follow symlinks which point somewhere inside the served directory
+    WithinRoot,
+    /// Never follow symlinks
+    Never,
+}
+
+impl FromStr for SymlinkPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "follow" => Ok(SymlinkPolicy::Follow),
+            "within-root" => Ok(SymlinkPolicy::WithinRoot),
+            "never" => Ok(SymlinkPolicy::Never),
+            _ => Err(format!(
+                "Unknown symlink policy `{}`, expected follow, within-root or never",
+                policy
+            )),
+        }
+    }
+}
+
+/// Decides which paths under the served directory are hidden from clients.
+///
+/// Dotfiles are hidden unless `--show-hidden` is given,
+/// as is anything matched by the ignore file
+/// and anything reached through a symlink the policy does not allow.
+/// Hidden paths are treated as though they do not exist,
+/// and so is everything beneath a hidden directory.
+pub struct Visibility {
+    root: PathBuf,
+    show_hidden: bool,
+    rules: Vec<IgnoreRule>,
+    symlinks: SymlinkPolicy,
+}
+
+impl Visibility {
+    /// Checks whether a path on disk is hidden
+    pub fn is_hidden(&self, path: &Path) -> bool {
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+
+        // `..` could climb out of the served directory whatever the policy
+        if relative
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            return true;
+        }
+
+        if !self.follows_symlinks(relative) {
+            return true;
+        }
+
+        let names = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if !self.show_hidden && names.iter().any(|name| name.starts_with('.')) {
+            return true;
+        }
+
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        // a path cannot be un-hidden if a directory above it is hidden,
+        // so every ancestor is checked in turn
+        let mut current = String::new();
+        for (i, name) in names.iter().enumerate() {
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(name);
+
+            let is_dir = i < names.len() - 1 || path.is_dir();
+            if self.is_ignored(current.as_str(), is_dir) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Checks whether a path relative to the root
+    /// only passes through symlinks which the policy allows,
+    /// and so stays inside the served directory.
+    ///
+    /// Only the parts of the path which already exist are checked,
+    /// so that files can still be created beneath allowed directories.
+    /// Symlinks whose targets do not exist are never followed,
+    /// as writing through them could create files anywhere.
+    fn follows_symlinks(&self, relative: &Path) -> bool {
+        if self.symlinks == SymlinkPolicy::Follow {
+            return true;
+        }
+
+        let root = match self.root.canonicalize() {
+            Ok(root) => root,
+            Err(_) => return false,
+        };
+
+        let inside_root = |path: &Path| {
+            path.canonicalize()
+                .is_ok_and(|target| target.starts_with(&root))
+        };
+
+        let mut current = self.root.clone();
+        let mut deepest = self.root.clone();
+        for component in relative.components() {
+            current.push(component);
+
+            let is_symlink = match fs::symlink_metadata(&current) {
+                Ok(metadata) => metadata.file_type().is_symlink(),
+                Err(_) => break,
+            };
+
+            if is_symlink && (self.symlinks == SymlinkPolicy::Never || !inside_root(&current)) {
+                return false;
+            }
+
+            deepest.clone_from(&current);
+        }
+
+        // wherever the path leads, what exists of it must be inside the root
+        inside_root(&deepest)
+    }
+
+    /// Checks a single path against the ignore rules,
+    /// where the last matching rule wins
+    fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// Responds with `404 Not Found` if a request's path is hidden,
+    /// exactly as if nothing were there.
+    pub fn check<'a>(&self, req: &Request) -> Option<Response<'a>> {
+        let path = self.root.join(req.uri.path.trim_start_matches('/'));
+
+        if self.is_hidden(&path) {
+            Some(Response::error(
+                StatusCode::NOT_FOUND,
+                Some(format!("File <code>{}</code> does not exist", req.uri.path).as_str()),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Reads the ignore file afresh,
+/// so that changes to it take effect without a restart.
+impl From<&Opts> for Visibility {
+    fn from(opts: &Opts) -> Self {
+        let root = PathBuf::from(opts.directory.as_str());
+
+        let rules = fs::read_to_string(root.join(IGNORE_FILE))
+            .map(|contents| contents.lines().filter_map(IgnoreRule::parse).collect())
+            .unwrap_or_default();
+
+        Visibility {
+            root,
+            show_hidden: opts.show_hidden,
+            rules,
+            symlinks: opts.symlinks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_utils::random_string;
+
+    fn visibility(root: &Path, symlinks: SymlinkPolicy) -> Visibility {
+        Visibility {
+            root: root.to_path_buf(),
+            show_hidden: false,
+            rules: Vec::new(),
+            symlinks,
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn keeps_paths_inside_the_root() {
+        use std::os::unix::fs::symlink;
+
+        let base = std::env::temp_dir().join(random_string(12));
+        let root = base.join("root");
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::write(base.join("outside.txt"), "").unwrap();
+        symlink(root.join("dir"), root.join("inside")).unwrap();
+        symlink(&base, root.join("escape")).unwrap();
+        symlink(base.join("missing"), root.join("dangling")).unwrap();
+
+        let within_root = visibility(&root, SymlinkPolicy::WithinRoot);
+        let never = visibility(&root, SymlinkPolicy::Never);
+        let follow = visibility(&root, SymlinkPolicy::Follow);
+
+        for visibility in &[&within_root, &never, &follow] {
+            assert!(visibility.is_hidden(&root.join("../outside.txt")));
+            assert!(visibility.is_hidden(&root.join("dir/../../outside.txt")));
+            assert!(!visibility.is_hidden(&root.join("dir/new.txt")));
+        }
+
+        assert!(!within_root.is_hidden(&root.join("inside/new.txt")));
+        assert!(within_root.is_hidden(&root.join("escape/outside.txt")));
+        assert!(within_root.is_hidden(&root.join("dangling/new.txt")));
+
+        assert!(never.is_hidden(&root.join("inside/new.txt")));
+
+        assert!(!follow.is_hidden(&root.join("escape/outside.txt")));
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}